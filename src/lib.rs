@@ -83,8 +83,229 @@
 //! # use std::fmt::Write as _;
 //! write!(w, "Hello, world!");
 //! ```
+//!
+//! The expansion is just the macro call, so its result can be handled as usual, for example with `?`:
+//!
+//! ```rust
+//! use docstr::docstr;
+//! use std::fmt::{self, Write as _};
+//!
+//! fn greet(w: &mut String, name: &str) -> fmt::Result {
+//!     docstr!(write! w,
+//!         /// Hello, {name}!
+//!     )?;
+//!     docstr!(write! w,
+//!         /// Bye!
+//!     )
+//! }
+//!
+//! let mut w = String::new();
+//! greet(&mut w, "Bob").unwrap();
+//! assert_eq!(w, "Hello, Bob!Bye!");
+//! ```
+//!
+//! This makes it possible to compare a value against the string with `assert_eq!`, which takes the string as its 2nd argument:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let actual = ["a", "b"].join("\n");
+//!
+//! docstr!(assert_eq! actual,
+//!     /// a
+//!     /// b
+//! );
+//! ```
+//!
+//! The path to the macro ends at the `!`, so arguments like the `target:` of [`log`](https://docs.rs/log)'s macros
+//! work too: `docstr!(log::info! target: "net", /// ...)` expands to `log::info!(target: "net", "...")`.
+//!
+//! Passing the string to `format_args!` avoids allocating a `String`.
+//! Like any `format_args!`, use it directly as an argument instead of storing it in a variable:
+//!
+//! ```rust
+//! use docstr::docstr;
+//! use std::fmt;
+//!
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! impl fmt::Display for Point {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         f.write_fmt(docstr!(format_args!
+//!             /// x = {}
+//!             /// y = {}
+//!             self.x, self.y
+//!         ))
+//!     }
+//! }
+//!
+//! assert_eq!(Point { x: 1, y: 2 }.to_string(), "x = 1\ny = 2");
+//! ```
+//!
+//! To make it clear where the output goes, the receiver can also be named with `#[into = ...]`,
+//! which is always passed as the 1st argument:
+//!
+//! ```rust
+//! # use std::fmt::Write as _;
+//! # use docstr::docstr;
+//! let mut w = String::new();
+//!
+//! docstr!(#[into = w] writeln!
+//!     /// Hello, world!
+//! );
+//!
+//! assert_eq!(w, "Hello, world!\n");
+//! ```
+//!
+//! The doc comments end at the first token which is not `#`. A `#` before the doc comments is passed to the macro
+//! if it isn't followed by `[...]`, but after a doc comment every `#` starts the next one. When the arguments after
+//! them start with `#`, like the `#[...]` attributes of arguments that some macros accept, end the doc comments
+//! explicitly with `=>`. Everything after `=>` is passed to the macro:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! macro_rules! tagged {
+//!     ($text:literal, #[tag = $tag:literal]) => {
+//!         concat!("[", $tag, "] ", $text)
+//!     };
+//! }
+//!
+//! let message = docstr!(tagged!
+//!     /// hello
+//!     => #[tag = "info"]
+//! );
+//!
+//! assert_eq!(message, "[info] hello");
+//! ```
+//!
+//! After the arguments, there can be a 2nd region of doc comments. It becomes a 2nd string,
+//! which is passed after the arguments:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let version = docstr!(concat!
+//!     /// docstr v
+//!     env!("CARGO_PKG_VERSION")
+//!     /// , built with
+//!     /// love
+//! );
+//!
+//! assert_eq!(version, concat!("docstr v", env!("CARGO_PKG_VERSION"), ", built with\nlove"));
+//! ```
+//!
+//! Expands to this:
+//!
+//! ```rust
+//! concat!("docstr v", env!("CARGO_PKG_VERSION"), ", built with\nlove");
+//! ```
+//!
+//! Modes are applied to the lines of both regions together, so `#[dedent]` removes the same indentation from all of them.
+//! Modes which change the whole string like `#[repeat = N]` can't be used with 2 regions.
+//! A `#[doc]` after `=>` doesn't start a 2nd region.
+//!
+//! # Explicit `#[doc]` attributes
+//!
+//! Doc comments `/// foo` are sugar for `#[doc = " foo"]` attributes, which can also be written directly.
+//! This is useful for macro-generated input. The literal can be any string literal, including a raw string.
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let explicit = docstr!(
+//!     #[doc = " foo"]
+//!     #[doc = r#" "bar""#]
+//!     /// baz
+//! );
+//!
+//! assert_eq!(explicit, "foo\n\"bar\"\nbaz");
+//! ```
+//!
+//! Just like with `///`, a single leading space is removed from each line.
+//!
+//! Adjacent string literals in the same attribute are concatenated, so
+//! `#[doc = "foo" "bar"]` is the same as `#[doc = "foobar"]`.
+//!
+//! Each attribute is exactly 1 line, no matter how the attributes are laid out in the source code.
+//! This makes it possible to write everything on a single line, e.g. in generated code:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let one_line = docstr!(#[doc = " a"] #[doc = " b"]);
+//!
+//! assert_eq!(one_line, "a\nb");
+//! ```
+//!
+//! A doc comment `///` always continues until the end of the line, so `/// a /// b` is a single line `a /// b`.
+//!
+//! Regular comments `// ...` and `/* ... */` are removed by the compiler before the macro sees its input,
+//! so they can be used between doc comments to annotate them, and don't become part of the string:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let query = docstr!(
+//!     /// SELECT name
+//!     // only active users, deleted ones are kept for auditing
+//!     /// FROM users WHERE active
+//! );
+//!
+//! assert_eq!(query, "SELECT name\nFROM users WHERE active");
+//! ```
+//!
+//! Be careful with 4 or more slashes: `//// ...` is a regular comment, not a doc comment.
+//! The line is silently left out, and the macro has no way to detect it:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let text = docstr!(
+//!     /// a
+//!     //// b
+//!     /// c
+//! );
+//!
+//! assert_eq!(text, "a\nc");
+//! ```
+//!
+//! To start a line with a slash, separate it from the doc comment with a space: `/// /b` is the line `/b`.
+//!
+//! # Strings known at runtime
+//!
+//! All of the processing happens at compile time, so the lines must be written in the source code.
+//! A crate with procedural macros can't export anything else, like a runtime builder.
+//! Without any modes, the string is the lines joined with `\n`, so the same string can be built at runtime:
+//!
+//! ```rust
+//! use docstr::docstr;
+//!
+//! let user = "ferris";
+//! let lines = ["Hello", user];
+//!
+//! assert_eq!(
+//!     docstr!(
+//!         /// Hello
+//!         /// ferris
+//!     ),
+//!     lines.join("\n")
+//! );
+//! ```
 
-use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use proc_macro::{
+    token_stream, Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree,
+};
+use std::iter::Peekable;
+
+mod modes;
+mod output;
+mod transform;
+
+use modes::{Case, Diff, ItemName, Modes, NormalizeNewlines, Output};
 
 /// Turns documentation comments into string at compile-time.
 ///
@@ -112,408 +333,3688 @@ use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenSt
 /// ```
 ///
 /// See the [crate-level](crate) documentation for more info
-#[proc_macro]
-pub fn docstr(input: TokenStream) -> TokenStream {
-    let mut input = input.into_iter().peekable();
-
-    // If we encounter any errors, we collect them into here
-    // and report them all at once
-    //
-    // compile_error!("you have done horrible things!")
-    let mut compile_errors = TokenStream::new();
-    let mut compile_error = |span: Span, message: &str| {
-        compile_errors.extend(CompileError::new(span, message));
-    };
-
-    // Path to the macro that we send tokens to.
-    //
-    // If this is `None`, this macro produces a string literal
-    let macro_ = match input.peek() {
-        Some(TokenTree::Punct(punct)) if *punct == '#' => {
-            // No macro, this will directly produce a string literal
-            None
-        }
-        // Ok, this is a path to a macro.
-        Some(_) => {
-            let mut macro_ = TokenStream::new();
-            // for better error messages
-            let mut last_is_ident = false;
-
-            // on the first compile error we stop trying to process the path because it won't
-            // make any sense after that
-            loop {
-                let tt = input.next();
-                match tt {
-                    // std::format!
-                    //            ^
-                    Some(TokenTree::Punct(exclamation)) if exclamation == '!' => {
-                        macro_.extend([TokenTree::Punct(exclamation)]);
-                        // end of the macro
-                        break;
-                    }
-                    // std::format!
-                    //    ^
-                    //     ^
-                    Some(TokenTree::Punct(colon)) if colon == ':' => {
-                        last_is_ident = false;
-                        macro_.extend([TokenTree::Punct(colon)]);
-                    }
-                    // std::format!
-                    // ^^^
-                    //      ^^^^^^
-                    Some(TokenTree::Ident(ident)) => {
-                        if last_is_ident {
-                            compile_error(ident.span(), &format!("2 identifiers in a row is not a valid macro path\n\ndid you mean one of:\n- `{macro_}::{ident}`\n- `{macro_}! {ident}`"));
-                            macro_ = TokenStream::new();
-                            break;
-                        }
+///
+/// # Conditional lines
+///
+/// A doc comment can be preceded by `#[cfg(...)]`, so that it is only included when the cfg holds:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let help: String = docstr!(
+///     /// Usage: app [OPTIONS]
+///     #[cfg(unix)]
+///     /// --daemon  Run in the background
+///     #[cfg(windows)]
+///     /// --service  Run as a Windows service
+///     /// --help  Print help
+/// );
+///
+/// # #[cfg(unix)]
+/// assert_eq!(help, "Usage: app [OPTIONS]\n--daemon  Run in the background\n--help  Print help");
+/// ```
+///
+/// Macros only see the `#[cfg(...)]` tokens, the compiler decides whether it holds. So the lines are joined at runtime,
+/// and `docstr!` evaluates to a [`String`] instead of a `&'static str`:
+///
+/// ```rust
+/// <[&str]>::join(&[
+///     "Usage: app [OPTIONS]",
+///     #[cfg(unix)]
+///     "--daemon  Run in the background",
+///     #[cfg(windows)]
+///     "--service  Run as a Windows service",
+///     "--help  Print help",
+/// ], "\n");
+/// ```
+///
+/// This can't be combined with a macro path, with outputs like `#[static_ref = NAME]`, or with modes which change
+/// the joined string like `#[repeat = N]`. Modes like `#[dedent]` look at all lines, whether they are included or not.
+/// Other macros like [`docstr_bytes!`] don't support it.
+///
+/// # Modes
+///
+/// Modes are attributes at the very start of the input, before the macro path.
+/// They change how the doc comments are processed, or what is generated.
+///
+/// Several modes can be written in one attribute, separated by `,`: `#[dedent, join = " "]` is the same as
+/// `#[dedent] #[join = " "]`. A value which contains a `,` itself must be a string or in parentheses.
+/// The modes can be followed by a `,` before the macro path.
+///
+/// The order of the modes doesn't matter, each mode is always applied at the same step:
+/// for example, `#[dedent]` always happens before `#[wrap = N]`, no matter which one comes first.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let name = "world";
+/// let greeting = docstr!(#[dedent, trim_blank_lines, join = " "], format!
+///     ///
+///     ///     hello
+///     ///     {name}
+///     ///
+/// );
+///
+/// assert_eq!(greeting, "hello world");
+/// ```
+///
+/// ## Short flags
+///
+/// The most common modes without a value can be combined into a group of short flags,
+/// written before all other modes:
+///
+/// | Flag | Mode                  |
+/// | ---- | --------------------- |
+/// | `d`  | `#[dedent]`           |
+/// | `t`  | `#[trim_blank_lines]` |
+/// | `r`  | `#[raw]`              |
+/// | `n`  | `#[number]`           |
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let short = docstr!([d, t]
+///     ///
+///     ///     fn main() {}
+///     ///
+/// );
+///
+/// let long = docstr!(#[dedent] #[trim_blank_lines]
+///     ///
+///     ///     fn main() {}
+///     ///
+/// );
+///
+/// assert_eq!(short, "fn main() {}");
+/// assert_eq!(short, long);
+/// ```
+///
+/// The group can be followed by a `,`, and by more modes: `docstr!([d], #[wrap = 80] ...)`.
+///
+/// ## `#[deflate = NAME]`
+///
+/// Requires the `compress` feature.
+///
+/// Compresses the string with raw DEFLATE and generates 2 constants instead of a string:
+/// `NAME` which contains the compressed bytes, and `NAME_LEN` which is
+/// the length of the string before compression. Use any DEFLATE decoder to get the string back at runtime.
+///
+/// ```rust
+/// # #[cfg(feature = "compress")] {
+/// use docstr::docstr;
+/// use std::io::Read as _;
+///
+/// docstr!(#[deflate = TEMPLATE]
+///     /// <html>
+///     ///     <body>Hello, world!</body>
+///     /// </html>
+/// );
+///
+/// let mut template = String::with_capacity(TEMPLATE_LEN);
+/// flate2::read::DeflateDecoder::new(TEMPLATE)
+///     .read_to_string(&mut template)
+///     .unwrap();
+///
+/// assert_eq!(template, "<html>\n    <body>Hello, world!</body>\n</html>");
+/// # }
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// const TEMPLATE: &[u8] = b"...";
+/// const TEMPLATE_LEN: usize = 45;
+/// ```
+///
+/// A visibility can be given as well: `#[deflate = pub(crate) TEMPLATE]`
+///
+/// ## `#[normalize_newlines]`
+///
+/// Removes `\r` from the end of each line, which is left there when content with
+/// Windows line endings is pasted into doc comments. With `#[normalize_newlines = all]`, removes every `\r`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let pasted = docstr!(#[normalize_newlines]
+///     #[doc = "foo\r"]
+///     #[doc = "bar\r"]
+/// );
+///
+/// assert_eq!(pasted, "foo\nbar");
+/// ```
+///
+/// ## `#[into = w]`
+///
+/// Passes `w` as the 1st argument to the macro, before any other tokens that come before the doc comments.
+/// See [Composition](crate#composition).
+///
+/// ## `#[strip_ansi]`
+///
+/// Removes ANSI escape sequences, like the colors in output pasted from a terminal. A sequence starts with
+/// the escape character and `[`, and ends with a letter: `\x1b[1;31m` is bold red, and `\x1b[0m` resets it.
+///
+/// Terminals print the actual escape character, which is invisible in most editors. So the escape character
+/// is also removed when it is written as `\x1b`, `\u{1b}`, `\033` or `\e`. In a `#[doc = "..."]` string literal,
+/// `\x1b` is already the actual escape character.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let output = docstr!(#[strip_ansi]
+///     /// \x1b[31merror\x1b[0m: not found
+///     /// \033[1;32mok\033[0m
+/// );
+///
+/// assert_eq!(output, "error: not found\nok");
+/// ```
+///
+/// ## `#[strip_comments = "#"]`
+///
+/// Removes every line which starts with the comment marker, ignoring indentation.
+/// Use it to annotate a script without the annotations ending up in the generated string.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let script = docstr!(#[strip_comments = "#"]
+///     /// # build in release mode, it is a lot faster
+///     /// cargo build --release
+///     ///     # then run it
+///     /// ./target/release/app
+/// );
+///
+/// assert_eq!(script, "cargo build --release\n./target/release/app");
+/// ```
+///
+/// The whole line is removed, comments after a command like `ls # files` are kept.
+///
+/// ## `#[trim_blank_lines]`
+///
+/// Removes blank lines at the start and at the end, which are often there to make the doc comments easier to read.
+/// Blank lines in the middle are kept. Lines which only contain whitespace are blank too.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let text = docstr!(#[trim_blank_lines]
+///     ///
+///     /// first paragraph
+///     ///
+///     /// second paragraph
+///     ///
+/// );
+///
+/// assert_eq!(text, "first paragraph\n\nsecond paragraph");
+/// ```
+///
+/// ## `#[dedent]`
+///
+/// Removes the indentation which all lines have in common, so the content can be indented to match the code around it.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let code = docstr!(#[dedent]
+///     ///     fn main() {
+///     ///         println!("Hello");
+///     ///     }
+/// );
+///
+/// assert_eq!(code, "fn main() {\n    println!(\"Hello\");\n}");
+/// ```
+///
+/// Blank lines are not taken into account, and lines which only contain whitespace become empty.
+///
+/// The indentation of every line must start with exactly the same whitespace as the least indented line.
+/// If the indentation mixes tabs and spaces inconsistently, there is no common indentation, so it is an error:
+///
+/// ```rust,compile_fail
+/// use docstr::docstr;
+///
+/// let code = docstr!(#[dedent]
+///     #[doc = "    indented with spaces"]
+///     #[doc = "\tindented with a tab"]
+/// );
+/// ```
+///
+/// ## `#[dedent_first]`
+///
+/// Removes the indentation of the first line which isn't blank from every line, like a heredoc
+/// is indented relative to its first line.
+///
+/// Lines which are indented more keep the extra indentation. Lines which are indented less,
+/// or don't start with exactly the same whitespace, are kept as-is.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let script = docstr!(#[dedent_first]
+///     ///     if true; then
+///     ///         echo "yes"
+///     ///     fi
+///     /// exit 0
+/// );
+///
+/// assert_eq!(script, "if true; then\n    echo \"yes\"\nfi\nexit 0");
+/// ```
+///
+/// Unlike `#[dedent]`, the result never depends on lines after the first one.
+///
+/// ## `#[lines = A..B]`
+///
+/// Only keeps the lines in the range `A..B`, before any other modes are applied. Like indices of a slice,
+/// the 1st line is `0` and the line `B` is not included. `A..=B`, `..B` and `A..` can be used as well.
+///
+/// This makes it possible to show only part of a larger example:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let body = docstr!(#[lines = 1..3]
+///     /// fn main() {
+///     ///     let x = 1;
+///     ///     println!("{x}");
+///     /// }
+/// );
+///
+/// assert_eq!(body, "    let x = 1;\n    println!(\"{x}\");");
+/// ```
+///
+/// It is an error if the range goes past the last line.
+///
+/// ## `#[raw]`
+///
+/// Generates a raw string literal like `r#"..."#` instead of a string literal where quotes, backslashes
+/// and newlines are escaped. The string is the same, but the expanded code is easier to read,
+/// e.g. with `cargo expand`. The literal has as few `#` as possible.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let expanded = docstr!(#[raw] #[trim_trailing_comma] stringify!
+///     /// C:\Users
+///     /// "quoted"
+/// );
+///
+/// assert_eq!(expanded, "r#\"C:\\Users\n\"quoted\"\"#");
+/// ```
+///
+/// Raw strings can't contain a carriage return `\r`, for those a string literal is generated as usual.
+///
+/// ## `#[with_location]`
+///
+/// Prefixes the string with the file and line of the invocation, like `src/main.rs:12 `.
+/// This is useful for error catalogs, where each message should say where it was defined.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let message = docstr!(#[with_location]
+///     /// config file is missing
+/// );
+///
+/// assert_eq!(message, format!("{}:{} config file is missing", file!(), line!() - 4));
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// concat!(file!(), ":", line!(), " ", "config file is missing");
+/// ```
+///
+/// It is still a `&'static str`. It can be passed to a macro like `format!`, which accepts `concat!` as its
+/// format string, but then variables can't be captured like `{name}`; use `{}` and pass them as arguments.
+/// It can't be used with outputs like `#[static_ref = NAME]`.
+///
+/// ## `#[strip = N]`
+///
+/// Removes up to `N` leading spaces from each line, instead of the single space after `///`.
+/// Lines with fewer spaces lose only the spaces they have.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let code = docstr!(#[strip = 5]
+///     ///     fn main() {
+///     ///         println!("Hello");
+///     ///     }
+/// );
+///
+/// assert_eq!(code, "fn main() {\n    println!(\"Hello\");\n}");
+/// ```
+///
+/// `#[strip = 0]` keeps every space, including the one after `///`.
+///
+/// ## `#[space_strip = all|one|none]`
+///
+/// Names the common choices of `#[strip = N]`, for porting from tools which strip the spaces differently:
+///
+/// | Mode                    | Leading spaces removed                |
+/// | ----------------------- | ------------------------------------- |
+/// | `#[space_strip = all]`  | all of them, like `str::trim_start`   |
+/// | `#[space_strip = one]`  | 1, like rustdoc. This is the default  |
+/// | `#[space_strip = none]` | none, like `#[strip = 0]`             |
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let list = docstr!(#[space_strip = all]
+///     ///   - milk
+///     /// - eggs
+/// );
+///
+/// assert_eq!(list, "- milk\n- eggs");
+/// ```
+///
+/// Only spaces are removed, not tabs. It can't be used together with `#[strip = N]`.
+///
+/// ## `#[trim_trailing_comma]`
+///
+/// By default, the string is always followed by a comma, and arguments after the doc comments
+/// are passed as they are. For example, `docstr!(format! /// {} "a",)` expands to `format!("{}", "a",)`.
+///
+/// This is fine for `format!`, but not every macro accepts a trailing comma.
+/// `#[trim_trailing_comma]` removes 1 trailing comma from the arguments, and doesn't add one after the string:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// macro_rules! pair {
+///     ($a:expr, $b:expr) => {
+///         ($a, $b)
+///     };
+/// }
+///
+/// let pair = docstr!(#[trim_trailing_comma] pair!
+///     /// first
+///     "second",
+/// );
+///
+/// assert_eq!(pair, ("first", "second"));
+/// ```
+///
+/// ## `#[at_placeholder]`
+///
+/// By default, the string is passed to the macro between the tokens before and after the doc comments.
+/// Some macros take the string in the middle of their arguments, which can be marked with `@`:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// macro_rules! labeled {
+///     ($label:expr, $text:expr, $width:expr) => {
+///         format!("{:>width$}: {}", $label, $text, width = $width)
+///     };
+/// }
+///
+/// let line = docstr!(#[at_placeholder] labeled! "name", @, 6
+///     /// docstr
+/// );
+///
+/// assert_eq!(line, "  name: docstr");
+/// ```
+///
+/// The tokens before and after the doc comments are joined with a comma, and `@` is replaced with the string.
+/// It must appear exactly once, as one of the arguments and not nested inside of brackets.
+///
+/// ## `#[explicit_trailing]`
+///
+/// Editors and formatters like `rustfmt` remove whitespace at the end of doc comments. To end a line
+/// with whitespace on purpose, put a `\` after it: `#[explicit_trailing]` removes 1 `\` at the end of each line.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let markdown = docstr!(#[explicit_trailing]
+///     /// line break  \
+///     /// in Markdown
+///     /// ends with a backslash \\
+/// );
+///
+/// assert_eq!(markdown, "line break  \nin Markdown\nends with a backslash \\");
+/// ```
+///
+/// So a line which should end with `\` needs to end with `\\`. Lines without a `\` at the end are kept as-is.
+///
+/// ## `#[tabs = N]`
+///
+/// Expands each tab to spaces, up to the next tab stop. Tab stops are every `N` columns.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let table = docstr!(#[tabs = 8]
+///     #[doc = "name\tage"]
+///     #[doc = "Bob\t21"]
+/// );
+///
+/// assert_eq!(table, "name    age\nBob     21");
+/// ```
+///
+/// ## `#[subst(NAME = "value")]`
+///
+/// Replaces each `{NAME}` with its value at compile-time, without [`format!`].
+/// The result is still a `&'static str`, so it can be used in constants.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// const USAGE: &str = docstr!(#[subst(BIN = "app", VERSION = "1.0")]
+///     /// {BIN} {VERSION}
+///     ///
+///     /// Usage: {BIN} [OPTIONS]
+/// );
+///
+/// assert_eq!(USAGE, "app 1.0\n\nUsage: app [OPTIONS]");
+/// ```
+///
+/// Only identifiers in braces are placeholders, so `{ "json": true }` is kept as-is.
+/// A placeholder without a value is an error, unless the string is passed to a macro like `format!`,
+/// in which case the macro handles it. `{{` is never the start of a placeholder.
+///
+/// ## `#[strip_doc_links]`
+///
+/// Replaces Markdown links with just their text, for reusing Rustdoc-style text as plain text.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let plain = docstr!(#[strip_doc_links]
+///     /// Returns a [`Vec`] of [users](https://example.com/users), see [the guide][guide].
+/// );
+///
+/// assert_eq!(plain, "Returns a Vec of users, see the guide.");
+/// ```
+///
+/// - `[text](url)` becomes `text`
+/// - `[text][reference]` becomes `text`
+/// - `[text]` becomes `text`
+///
+/// When the text is surrounded by backticks, they are removed too.
+/// Each link must be on a single line, and reference definitions like `[guide]: https://...` are kept.
+///
+/// ## `#[placeholder_style = printf]`
+///
+/// Converts printf-style placeholders to Rust's, for migrating existing printf templates to [`format!`].
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let report = docstr!(#[placeholder_style = printf] format!
+///     /// %s scored %d points (100%%), id: %#06x {unchanged}
+///     "Alice", 42, 255
+/// );
+///
+/// assert_eq!(report, "Alice scored 42 points (100%), id: ff {unchanged}");
+/// ```
+///
+/// | printf | Rust |
+/// |--------|------|
+/// | `%d` `%i` `%u` `%s` `%c` `%f` `%F` `%g` `%G` | `{}` |
+/// | `%x` `%X` `%o` `%e` `%E` `%p` | `{:x}` `{:X}` `{:o}` `{:e}` `{:E}` `{:p}` |
+/// | `%%` | `%` |
+/// | `{` `}` | `{{` `}}` |
+///
+/// Flags, width, precision and length modifiers are dropped, so `%-08.3lf` becomes `{}`.
+/// Any other placeholder is an error.
+///
+/// ## `#[reverse_lines]`
+///
+/// Reverses the order of the lines. Blank lines at the end are kept at the end,
+/// so a string ending with a newline still ends with one.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let stack_trace = docstr!(#[reverse_lines]
+///     /// main
+///     /// run
+///     /// panic
+///     ///
+/// );
+///
+/// assert_eq!(stack_trace, "panic\nrun\nmain\n");
+/// ```
+///
+/// ## `#[brace_unescape]`
+///
+/// Without a macro, braces are kept as they are. With `#[brace_unescape]`, `{{` becomes `{` and `}}` becomes `}`,
+/// just like `format!` would do it. So the same doc comments produce the same string
+/// with and without `format!`, as long as they don't have any placeholders:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let literal = docstr!(#[brace_unescape]
+///     /// fn main() {{}}
+/// );
+/// let formatted = docstr!(format!
+///     /// fn main() {{}}
+/// );
+///
+/// assert_eq!(literal, "fn main() {}");
+/// assert_eq!(literal, formatted);
+/// ```
+///
+/// A single `{` or `}` is an error, because with `format!` it would be a placeholder.
+/// Using `#[brace_unescape]` together with a macro is also an error.
+///
+/// ## `#[wrap = N]`
+///
+/// Wraps each line at word boundaries, so that no line is wider than `N` characters.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let help = docstr!(#[wrap = 30]
+///     /// Usage: app [OPTIONS]
+///     ///
+///     /// Prints the configuration and exits, without starting the server
+/// );
+///
+/// assert_eq!(help, "\
+/// Usage: app [OPTIONS]
+///
+/// Prints the configuration and
+/// exits, without starting the
+/// server");
+/// ```
+///
+/// Lines are only broken at whitespace. Words wider than `N`, such as long URLs like
+/// `https://example.com/a/very/long/path`, are never split and overflow instead, so links keep working.
+/// Lines which already fit, including blank lines, are left untouched.
+///
+/// ## `#[hang = M]`
+///
+/// With `#[wrap = N]`, indents each line that was wrapped onto a new line by `M` spaces, so lists read nicely.
+/// The hanging indent counts towards the width `N`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let help = docstr!(#[wrap = 30] #[hang = 4]
+///     /// --config: path to the configuration file
+///     ///
+///     /// --quiet: no output
+/// );
+///
+/// assert_eq!(help, "\
+/// --config: path to the
+///     configuration file
+///
+/// --quiet: no output");
+/// ```
+///
+/// ## `#[wrap_preserve_indent]`
+///
+/// With `#[wrap = N]`, starts each line that was wrapped onto a new line with the indentation
+/// of the original line, so indented paragraphs stay aligned. Combined with `#[hang = M]`,
+/// the `M` spaces are added after that indentation.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let help = docstr!(#[wrap = 24] #[wrap_preserve_indent]
+///     /// Options:
+///     ///     --verbose prints every step that is taken
+/// );
+///
+/// assert_eq!(help, "\
+/// Options:
+///     --verbose prints
+///     every step that is
+///     taken");
+/// ```
+///
+/// ## `#[emit_toc]`
+///
+/// Prepends a table of contents to Markdown content: a nested list with a link to every heading,
+/// followed by a blank line. Anchors are generated the same way GitHub does it.
+/// Headings in fenced code blocks are not included.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let readme = docstr!(#[emit_toc]
+///     /// # Getting Started
+///     ///
+///     /// ## Install
+///     ///
+///     /// ## What's next?
+///     ///
+///     /// # API
+/// );
+///
+/// assert_eq!(readme, "\
+/// - [Getting Started](#getting-started)
+///   - [Install](#install)
+///   - [What's next?](#whats-next)
+/// - [API](#api)
+///
+/// \x23 Getting Started
+///
+/// \x23# Install
+///
+/// \x23# What's next?
+///
+/// \x23 API");
+/// ```
+///
+/// ## `#[escape_for_makefile]`
+///
+/// Doubles every `$` to `$$`, so that literal dollar signs survive Make's variable expansion
+/// when the content is written to a `Makefile`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let rule = docstr!(#[escape_for_makefile]
+///     /// print:
+///     #[doc = "\techo \"$HOME costs $5\""]
+/// );
+///
+/// assert_eq!(rule, "print:\n\techo \"$$HOME costs $$5\"");
+/// ```
+///
+/// Recipe lines in a `Makefile` must start with a tab. This mode doesn't check or fix that,
+/// so make sure to indent them with a tab and not spaces. `#[tabs = N]` would expand them to spaces.
+///
+/// ## `#[number]`
+///
+/// Prefixes each line with its number, like `1: `. Useful for showing source code in error messages.
+///
+/// - `#[number_from = N]` starts counting at `N` instead of 1
+/// - `#[number_format = "{n}| "]` changes the prefix, `{n}` is replaced with the number
+/// - `#[number_skip_blank]` doesn't number empty lines, they don't get a prefix either
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let listing = docstr!(#[number] #[number_from = 9] #[number_format = "{n} | "]
+///     /// fn main() {
+///     ///     panic!();
+///     /// }
+/// );
+///
+/// assert_eq!(listing, "9 | fn main() {\n10 |     panic!();\n11 | }");
+/// ```
+///
+/// ## `#[line_hash_comments]`
+///
+/// Appends ` # crc:xxxxxxxx` to each line, where `xxxxxxxx` is the CRC-32 of that line in hex.
+/// When the generated content ends up in a diff, this shows exactly which lines changed.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let audited = docstr!(#[line_hash_comments]
+///     /// foo
+///     /// bar
+/// );
+///
+/// assert_eq!(audited, "foo # crc:8c736521\nbar # crc:76ff8caa");
+/// ```
+///
+/// ## `#[as_diff = added]`
+///
+/// Prefixes every line with `+`, or with `-` for `#[as_diff = removed]`, like the lines of a unified diff.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let added = docstr!(#[as_diff = added]
+///     /// fn new() {}
+///     /// fn old() {}
+/// );
+///
+/// assert_eq!(added, "+fn new() {}\n+fn old() {}");
+/// ```
+///
+/// ## `#[diff_markers]`
+///
+/// For writing a unified diff by hand: lines which start with `+`, `-` or a space are kept as-is,
+/// and every other line is context, so it is prefixed with a space.
+/// A different prefix can be given with `#[diff_markers = "+"]`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let diff = docstr!(#[diff_markers] #[strip = 0]
+///     ///fn main() {
+///     ///-    println!("old");
+///     ///+    println!("new");
+///     ///}
+/// );
+///
+/// assert_eq!(diff, " fn main() {\n-    println!(\"old\");\n+    println!(\"new\");\n }");
+/// ```
+///
+/// ## `#[validate_no_trailing_whitespace]`
+///
+/// Reports an error for every line which ends with whitespace, after all other modes have transformed the lines.
+/// Use it to make sure that generated files are clean.
+///
+/// ```rust,compile_fail
+/// use docstr::docstr;
+///
+/// let dirty = docstr!(#[validate_no_trailing_whitespace]
+///     #[doc = "trailing space "]
+/// );
+/// ```
+///
+/// ## `#[allow_empty]`
+///
+/// Without any doc comments, `docstr!` reports an error. For input generated by other macros,
+/// where zero lines can be legitimate, `#[allow_empty]` generates an empty string instead:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// macro_rules! text {
+///     ($($line:literal)*) => {
+///         docstr!(#[allow_empty] $(#[doc = $line])*)
+///     };
+/// }
+///
+/// assert_eq!(text!(" a" " b"), "a\nb");
+/// assert_eq!(text!(), "");
+/// ```
+///
+/// Only the missing doc comments are allowed, malformed doc comments are still errors.
+///
+/// ## `#[validate = json]`
+///
+/// Parses the lines as JSON, and reports an error at the line where it is invalid.
+/// The string itself is unchanged, this only catches mistakes in embedded JSON at compile time.
+///
+/// Requires the `json` feature.
+///
+/// ```rust
+/// # #[cfg(feature = "json")] {
+/// use docstr::docstr;
+///
+/// let config = docstr!(#[validate = json]
+///     /// {
+///     ///     "name": "docstr",
+///     ///     "tags": ["string", "literal"]
+///     /// }
+/// );
+///
+/// assert!(config.starts_with('{'));
+/// # }
+/// ```
+///
+/// This would fail to compile, pointing at the missing `,`:
+///
+/// ```rust,ignore
+/// docstr!(#[validate = json]
+///     /// {
+///     ///     "name": "docstr"
+///     ///     "tags": []
+///     /// }
+/// );
+/// ```
+///
+/// ## `#[check_braces]`
+///
+/// Without a macro, `{name}` is not interpolated. A lone `{` or `}` usually means that `format!` was forgotten,
+/// or that braces were removed by mistake. `#[check_braces]` reports an error for each `{` which is never
+/// closed, and each `}` which is never opened. Braces can be closed on a different line.
+///
+/// ```rust,compile_fail
+/// use docstr::docstr;
+///
+/// let code = docstr!(#[check_braces]
+///     /// fn main() {
+///     ///     println!("hello");
+/// );
+/// ```
+///
+/// `{{` and `}}` are escaped braces, so they are skipped. This means that nested braces like in the JSON
+/// `{"a":{}}` are reported, because `}}` doesn't close anything. Put a space between them instead.
+///
+/// ## `#[join = "separator"]`
+///
+/// Joins the lines with `separator` instead of a newline. With an empty separator,
+/// a long single-line string can be split across readable source lines:
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let json = docstr!(#[join = ""]
+///     /// {"name":"Bob",
+///     /// "age":21,
+///     /// "admin":false}
+/// );
+///
+/// assert_eq!(json, r#"{"name":"Bob","age":21,"admin":false}"#);
+/// ```
+///
+/// No spaces are added between the lines, so any spacing has to be part of the lines themselves.
+///
+/// ## `#[repeat = N]`
+///
+/// Repeats the lines `N` times. Repetitions are separated by a newline, just like the lines,
+/// or by the separator of `#[join = "separator"]`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let rows = docstr!(#[repeat = 3]
+///     /// <tr>
+///     /// </tr>
+/// );
+///
+/// assert_eq!(rows, "<tr>\n</tr>\n<tr>\n</tr>\n<tr>\n</tr>");
+/// ```
+///
+/// ## `#[upper]` and `#[lower]`
+///
+/// Converts the string to uppercase or lowercase at compile-time, so it stays a `&'static str`.
+/// Non-ASCII characters are converted too, using [`str::to_uppercase`] and [`str::to_lowercase`].
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let query = docstr!(#[upper]
+///     /// select name
+///     /// from users
+/// );
+/// let name = docstr!(#[lower]
+///     /// ÜBER_CONFIG
+/// );
+///
+/// assert_eq!(query, "SELECT NAME\nFROM USERS");
+/// assert_eq!(name, "über_config");
+/// ```
+///
+/// The whole string is converted, so with a macro like `format!` this includes placeholders
+/// like `{name}`, which would then refer to a different variable.
+///
+/// ## `#[nfc]` and `#[nfd]`
+///
+/// Normalizes the string to the Unicode normalization form NFC (composed) or NFD (decomposed) at compile-time,
+/// so it stays a `&'static str`. The same text can be typed as different characters, like `é` and
+/// `e` followed by a combining accent. After normalizing, they compare equal.
+///
+/// Requires the `normalize` feature.
+///
+/// ```rust
+/// # #[cfg(feature = "normalize")] {
+/// use docstr::docstr;
+///
+/// // `e` followed by U+0301 COMBINING ACUTE ACCENT
+/// let greeting = docstr!(#[nfc]
+///     /// café
+/// );
+///
+/// assert_eq!(greeting, "caf\u{e9}");
+/// # }
+/// ```
+///
+/// ## `#[leading_newline]`
+///
+/// Makes sure that the content starts with a newline, which is useful for banners printed after other output.
+/// If the first line is already blank, no other newline is added.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let banner = docstr!(#[leading_newline]
+///     /// === Server started ===
+/// );
+/// let already_blank = docstr!(#[leading_newline]
+///     ///
+///     /// === Server started ===
+/// );
+///
+/// assert_eq!(banner, "\n=== Server started ===");
+/// assert_eq!(banner, already_blank);
+/// ```
+///
+/// ## `#[strip_final_newline]`
+///
+/// Removes the `\n` at the end of the content, if the last line is blank. Macros like [`eprintln!`]
+/// add a newline of their own, so this avoids printing an empty line after the message:
+///
+/// ```rust
+/// use docstr::docstr;
+/// use std::fmt::Write as _;
+///
+/// let mut log = String::new();
+///
+/// docstr!(#[strip_final_newline] writeln! log,
+///     /// warning: the config file is missing
+///     ///
+/// )
+/// .unwrap();
+///
+/// assert_eq!(log, "warning: the config file is missing\n");
+/// ```
+///
+/// Only 1 newline is removed.
+///
+/// ## `#[ensure_shebang = "/bin/sh"]`
+///
+/// Adds `#!/bin/sh` as the first line, unless the content already starts with a shebang `#!`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let script = docstr!(#[ensure_shebang = "/usr/bin/env bash"]
+///     /// echo "Hello, world!"
+/// );
+///
+/// assert_eq!(script, "#!/usr/bin/env bash\necho \"Hello, world!\"");
+/// ```
+///
+/// ## `#[emit_raw_bytes_hex_dump]`
+///
+/// Replaces the content with a canonical hex dump of its bytes, in the same format as `hexdump -C`:
+/// offset, 16 bytes in hex, then the printable ASCII characters. The last line is the total length.
+/// Repeated lines are not collapsed.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let dump = docstr!(#[emit_raw_bytes_hex_dump]
+///     /// Hello, world!
+///     /// foo
+/// );
+///
+/// assert_eq!(dump, "\
+/// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 66 6f  |Hello, world!.fo|
+/// 00000010  6f                                                |o|
+/// 00000011");
+/// ```
+///
+/// This is applied after all other modes which transform the content.
+///
+/// ## `#[wrap_in("open", "close")]`
+///
+/// Adds the 1st string before the content, and the 2nd string after it.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let comment = docstr!(#[wrap_in("<!-- ", " -->")]
+///     /// generated file,
+///     /// do not edit
+/// );
+///
+/// assert_eq!(comment, "<!-- generated file,\ndo not edit -->");
+/// ```
+///
+/// ## `#[emit_const_str_slice_of_bytes = NAME]`
+///
+/// Generates a constant `NAME` with the bytes of the string, where each byte is a hex integer literal.
+/// Unlike `b"..."`, nothing in it is escaped.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[emit_const_str_slice_of_bytes = HELLO]
+///     /// Hi
+///     /// !
+/// );
+///
+/// assert_eq!(HELLO, b"Hi\n!");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// const HELLO: &[u8] = &[0x48, 0x69, 0x0A, 0x21];
+/// ```
+///
+/// ## `#[to_const_option = NAME]`
+///
+/// Generates a constant `NAME` of type `Option<&str>`, which is `None` when the string is empty,
+/// saving a check for emptiness at runtime.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[to_const_option = HELP]
+///     /// Usage: app
+/// );
+///
+/// docstr!(#[to_const_option = pub FOOTER]
+///     #[doc = ""]
+/// );
+///
+/// assert_eq!(HELP, Some("Usage: app"));
+/// assert_eq!(FOOTER, None);
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// const HELP: Option<&str> = Some("Usage: app");
+/// pub const FOOTER: Option<&str> = None;
+/// ```
+///
+/// ## `#[emit_const_array_2d = NAME]`
+///
+/// Generates a constant `NAME` of type `&[&[&str]]` for embedding tables.
+/// Each line is a row, and its cells are separated by `|`. Cells are trimmed,
+/// and the `|` at the start and end of a row are optional. Blank lines are ignored.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[emit_const_array_2d = USERS]
+///     /// | name  | age | role  |
+///     /// | Alice | 30  | admin |
+///     /// | Bob   | 25  |
+/// );
+///
+/// assert_eq!(USERS[1], ["Alice", "30", "admin"]);
+/// assert_eq!(USERS[2][2], "");
+/// ```
+///
+/// Rows with fewer cells are padded with empty strings, so every row has the same number of columns.
+/// The above expands to this:
+///
+/// ```rust
+/// const USERS: &[&[&str]] = &[
+///     &["name", "age", "role"],
+///     &["Alice", "30", "admin"],
+///     &["Bob", "25", ""],
+/// ];
+/// ```
+///
+/// ## `#[measure_const = NAME]`
+///
+/// Generates a constant `NAME` of type `(usize, usize)` with the dimensions of the content after all modes
+/// have been applied: the number of characters in the widest line, and the number of lines.
+/// This is useful for allocating a buffer up front, e.g. for a terminal UI.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[measure_const = LOGO_SIZE]
+///     /// +------+
+///     /// | logo |
+///     /// +------+
+/// );
+///
+/// const COLUMNS: usize = LOGO_SIZE.0;
+/// const ROWS: usize = LOGO_SIZE.1;
+///
+/// let buffer = [[' '; COLUMNS]; ROWS];
+/// assert_eq!(LOGO_SIZE, (8, 3));
+/// ```
+///
+/// Characters are counted as [`char`]s, so wide characters such as emoji still count as 1 column.
+///
+/// ## `#[emit_static_str_map_sorted = NAME]`
+///
+/// Generates a constant `NAME` of type `&[(&str, &str)]` from `key: value` lines, sorted by the key,
+/// so it can be searched with [`binary_search_by_key`](slice::binary_search_by_key).
+/// Keys and values are trimmed, and blank lines are ignored.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[emit_static_str_map_sorted = MIME_TYPES]
+///     /// png: image/png
+///     /// html: text/html
+///     /// css: text/css
+/// );
+///
+/// assert_eq!(MIME_TYPES[0], ("css", "text/css"));
+///
+/// let html = MIME_TYPES.binary_search_by_key(&"html", |(key, _)| key);
+/// assert_eq!(MIME_TYPES[html.unwrap()].1, "text/html");
+/// ```
+///
+/// The key ends at the first `:`, so the value can contain more of them. Use `#[delimiter = "="]` to split on
+/// something else. Duplicate keys are kept in the order they were written, unless `#[strict_keys]` is used,
+/// which makes them an error.
+///
+/// ## `#[emit_const_u8_slices_per_line = NAME]`
+///
+/// Generates a constant `NAME` of type `&[&[u8]]`, with the bytes of each line. Blank lines become `b""`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[emit_const_u8_slices_per_line = PACKETS]
+///     /// PING
+///     ///
+///     /// PONG
+/// );
+///
+/// assert_eq!(PACKETS, [&b"PING"[..], b"", b"PONG"]);
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// const PACKETS: &[&[u8]] = &[b"PING", b"", b"PONG"];
+/// ```
+///
+/// ## `#[static_ref = NAME]`
+///
+/// Generates a `static` named `NAME` of type `&str`. Unlike a `const`, which is copied into every place
+/// that uses it, a `static` has a single address, so the string is stored once no matter how often
+/// `NAME` is used.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[static_ref = pub BANNER]
+///     /// // This file is generated, do not edit it
+/// );
+///
+/// let first = BANNER;
+/// let second = BANNER;
+///
+/// assert_eq!(BANNER, "// This file is generated, do not edit it");
+/// assert_eq!(first.as_ptr(), second.as_ptr());
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// pub static BANNER: &str = "// This file is generated, do not edit it";
+/// ```
+///
+/// Each `#[static_ref = NAME]` defines a new `static`, so using the same `NAME` twice in one module
+/// is an error, just like defining any other item twice. Define it once, and refer to it by name.
+///
+/// A `static` is not needed to make large strings smaller in the binary. A string literal is not copied
+/// into the code at each use, the code only refers to it, and the compiler stores identical literals once.
+/// A debug build with 20 `docstr!` of the same 55 KB of text is less than 1 KB larger than with 1 of them.
+///
+/// ## `#[named = NAME]`
+///
+/// Generates a `const` named `NAME` of type `&str`, and guards against the same string being defined twice,
+/// for example by copy-pasting a large constant and forgetting to change it.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[named = pub LICENSE]
+///     /// Licensed under MIT or Apache-2.0
+/// );
+///
+/// assert_eq!(LICENSE, "Licensed under MIT or Apache-2.0");
+/// ```
+///
+/// Next to the `const`, it generates a hidden item which is named after the 64-bit FNV-1a hash of the string,
+/// in upper case hex: `__DOCSTR_NAMED_{hash}`. A second `#[named]` with the same string in this module
+/// defines that item again, which the compiler reports as an error, even when the 2nd `NAME` is different:
+///
+/// ```rust,compile_fail
+/// use docstr::docstr;
+///
+/// docstr!(#[named = LICENSE]
+///     /// Licensed under MIT or Apache-2.0
+/// );
+///
+/// // error[E0428]: the name `__DOCSTR_NAMED_...` is defined multiple times
+/// docstr!(#[named = COPY]
+///     /// Licensed under MIT or Apache-2.0
+/// );
+/// ```
+///
+/// ## `#[chunk_lines(N)]`
+///
+/// Splits the content into chunks of `N` lines, and generates a `&'static [&'static str]` of them.
+/// The last chunk has fewer lines if the number of lines is not divisible by `N`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let pages: &[&str] = docstr!(#[chunk_lines(2)]
+///     /// 1
+///     /// 2
+///     /// 3
+/// );
+///
+/// assert_eq!(pages, ["1\n2", "3"]);
+/// ```
+///
+/// ## `#[emit_match_arms]`
+///
+/// Each line is a match arm. Rust doesn't allow macros in place of match arms,
+/// so `docstr!` generates the whole `match`: tokens before the doc comments are the expression to match on.
+/// Blank lines are ignored.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// fn http_status(code: u16) -> &'static str {
+///     docstr!(#[emit_match_arms] code
+///         /// 200 => "OK"
+///         /// 404 => "Not Found"
+///         /// _ => "Unknown"
+///     )
+/// }
+///
+/// assert_eq!(http_status(404), "Not Found");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let code = 200;
+/// match code {
+///     200 => "OK",
+///     404 => "Not Found",
+///     _ => "Unknown",
+/// }
+/// # ;
+/// ```
+///
+/// ## `#[emit_enum_variants]`
+///
+/// Each line is the name of a variant. Rust doesn't allow macros inside of an `enum`,
+/// so `docstr!` generates the whole `enum`: tokens before the doc comments are its header.
+/// Blank lines are ignored.
+///
+/// Attributes for the `enum` go inside of the mode, separated by commas.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// docstr!(#[emit_enum_variants(derive(Debug, PartialEq), repr(u8))] pub enum Color
+///     /// Red
+///     /// Green
+///     /// Blue
+/// );
+///
+/// assert_eq!(Color::Green as u8, 1);
+/// assert_ne!(Color::Red, Color::Blue);
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// #[derive(Debug, PartialEq)]
+/// #[repr(u8)]
+/// pub enum Color {
+///     Red,
+///     Green,
+///     Blue,
+/// }
+/// ```
+///
+/// A line which is not a single identifier is an error.
+///
+/// ## `#[emit_byte_match]`
+///
+/// Like `#[emit_match_arms]`, but for matching on a `u8`. Each literal in a pattern must be a byte,
+/// like `0x06`, `b'a'` or `255`, otherwise it is an error which points at the line.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// fn control(byte: u8) -> &'static str {
+///     docstr!(#[emit_byte_match] byte
+///         /// 0x06 => "ACK"
+///         /// 0x15 => "NAK"
+///         /// b'0'..=b'9' => "digit"
+///         /// _ => "other"
+///     )
+/// }
+///
+/// assert_eq!(control(0x06), "ACK");
+/// assert_eq!(control(b'7'), "digit");
+/// assert_eq!(control(0xFF), "other");
+/// ```
+///
+/// ## `#[call = function]`
+///
+/// Passes the string to a function or method, instead of a macro. It is the only argument.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// struct Builder {
+///     text: String,
+/// }
+///
+/// impl Builder {
+///     fn with_text(self, text: &str) -> Self {
+///         Self { text: self.text + text }
+///     }
+/// }
+///
+/// let builder = Builder { text: String::new() };
+///
+/// let builder = docstr!(#[call = builder.with_text]
+///     /// Hello
+///     /// world
+/// );
+///
+/// let owned = docstr!(#[call = String::from]
+///     /// Hello
+/// );
+///
+/// assert_eq!(builder.text, "Hello\nworld");
+/// assert_eq!(owned, "Hello");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # struct Builder;
+/// # impl Builder { fn with_text(self, text: &str) -> Self { self } }
+/// # let builder = Builder;
+/// builder.with_text("Hello\nworld");
+/// String::from("Hello");
+/// ```
+///
+/// It is a mode and not a path like `docstr!(builder.with_text /// ...)`, so it can't be confused with the path to a macro.
+///
+/// ## `#[join_expr = sep]`
+///
+/// Joins the lines with `sep` at runtime, for separators which are not known at compile time.
+/// Unlike `#[join = "separator"]`, the result is an owned [`String`] and not a `&'static str`.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let separator = if cfg!(windows) { "\r\n" } else { "\n" };
+///
+/// let text: String = docstr!(#[join_expr = separator]
+///     /// a
+///     /// b
+/// );
+///
+/// assert_eq!(text, ["a", "b"].join(separator));
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let separator = "\n";
+/// ["a", "b"].join(separator);
+/// ```
+///
+/// `sep` can be any expression of type `&str`.
+///
+/// ## `#[extend = buf]`
+///
+/// Appends the string to the [`String`] `buf` with [`String::push_str`], instead of producing a literal.
+///
+/// ```rust
+/// use docstr::docstr;
+///
+/// let mut html = String::from("<!DOCTYPE html>\n");
+///
+/// docstr!(#[extend = html]
+///     /// <p>Hello</p>
+/// );
+///
+/// assert_eq!(html, "<!DOCTYPE html>\n<p>Hello</p>");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let mut html = String::new();
+/// html.push_str("<p>Hello</p>");
+/// ```
+///
+/// ## `#[write_all = io]`
+///
+/// Writes the string to the [`std::io::Write`] `io` with [`write_all`](std::io::Write::write_all),
+/// and evaluates to the [`std::io::Result`] of it. See [`docstr_bytes!`](crate::docstr_bytes#write_all--io).
+///
+/// ## `#[emit_writeln_sequence]`
+///
+/// Calls [`writeln!`] once for each line, instead of passing the whole string to a single `writeln!`.
+/// The writer goes before the doc comments. Each call is followed by `?`.
+///
+/// ```rust
+/// use docstr::docstr;
+/// use std::fmt::Write as _;
+///
+/// fn render(out: &mut String, name: &str) -> std::fmt::Result {
+///     docstr!(#[emit_writeln_sequence] out,
+///         /// Hello, {name}!
+///         /// Goodbye.
+///     );
+///     Ok(())
+/// }
+///
+/// let mut out = String::new();
+/// render(&mut out, "Bob").unwrap();
+/// assert_eq!(out, "Hello, Bob!\nGoodbye.\n");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # use std::fmt::Write as _;
+/// # fn render(out: &mut String, name: &str) -> std::fmt::Result {
+/// {
+///     writeln!(out, "Hello, {name}!")?;
+///     writeln!(out, "Goodbye.")?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Each line is a format string, so `{` and `}` must be escaped as `{{` and `}}`.
+/// The writer is repeated in every call, so it should be a place like `out` and not an expression like `open_file()?`.
+#[proc_macro]
+pub fn docstr(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_no_unescape("`docstr!`", compile_error);
+        if !matches!(docstr.modes.output, Some((_, Output::SortedMap(_)))) {
+            docstr.expect_no_delimiter("`docstr!`", compile_error);
+        }
+        if docstr.has_cfg_lines() {
+            return docstr.cfg_lines(compile_error);
+        }
+        let string = docstr.string();
+
+        if let (Some((name, _)), Some(_)) = (&docstr.modes.output, &docstr.middle) {
+            compile_error(
+                name.span(),
+                &format!("`#[{name}]` can't be used together with a 2nd region of doc comments"),
+            );
+            return TokenStream::new();
+        }
+        if let (Some((name, _)), Some(span)) = (&docstr.modes.output, docstr.modes.with_location) {
+            compile_error(
+                span,
+                &format!("`#[with_location]` can't be used together with `#[{name}]`"),
+            );
+        }
+
+        match &docstr.modes.output {
+            Some((_, Output::Deflate(name))) => {
+                docstr.expect_only_doc_comments("`#[deflate]`", compile_error);
+                return output::deflate_items(name, &string);
+            }
+            Some((name, Output::MatchArms)) => {
+                return output::match_arms(&docstr, name, false, compile_error);
+            }
+            Some((_, Output::EnumVariants(attributes))) => {
+                return output::enum_item(&docstr, attributes, compile_error);
+            }
+            Some((name, Output::ByteMatch)) => {
+                return output::match_arms(&docstr, name, true, compile_error);
+            }
+            Some((_, Output::Call(function))) => {
+                docstr.expect_only_doc_comments("`#[call]`", compile_error);
+                // builder.text("foo\nbar")
+                let mut call = function.clone();
+                call.extend([TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    TokenTree::Literal(Literal::string(&string)).into(),
+                ))]);
+                return call;
+            }
+            Some((name, Output::JoinExpr(separator))) => {
+                docstr.expect_only_doc_comments("`#[join_expr]`", compile_error);
+                if docstr.modes.join.is_some() {
+                    compile_error(
+                        name.span(),
+                        "`#[join_expr]` can't be used together with `#[join]`",
+                    );
+                }
+                return output::join_expr(&string, separator);
+            }
+            Some((_, Output::Extend(target))) => {
+                docstr.expect_only_doc_comments("`#[extend]`", compile_error);
+                return output::method_call(
+                    target,
+                    "push_str",
+                    TokenTree::Literal(Literal::string(&string)),
+                );
+            }
+            Some((_, Output::WriteAll(target))) => {
+                docstr.expect_only_doc_comments("`#[write_all]`", compile_error);
+                return output::method_call(
+                    target,
+                    "write_all",
+                    TokenTree::Literal(Literal::byte_string(string.as_bytes())),
+                );
+            }
+            Some((_, Output::WritelnSequence)) => {
+                return output::writeln_sequence(&docstr, &string, compile_error);
+            }
+            Some((_, Output::ConstOption(name))) => {
+                docstr.expect_only_doc_comments("`#[to_const_option]`", compile_error);
+                return output::option_item(name, &string);
+            }
+            Some((_, Output::Array2d(name))) => {
+                docstr.expect_only_doc_comments("`#[emit_const_array_2d]`", compile_error);
+                return output::array_2d_item(name, &string);
+            }
+            Some((_, Output::Measure(name))) => {
+                docstr.expect_only_doc_comments("`#[measure_const]`", compile_error);
+                return output::measure_item(name, &string);
+            }
+            Some((_, Output::SortedMap(name))) => {
+                docstr.expect_only_doc_comments("`#[emit_static_str_map_sorted]`", compile_error);
+                return output::sorted_map_item(&docstr, name, compile_error);
+            }
+            Some((_, Output::ByteLines(name))) => {
+                docstr
+                    .expect_only_doc_comments("`#[emit_const_u8_slices_per_line]`", compile_error);
+                return output::byte_lines_item(name, &string);
+            }
+            Some((_, Output::Static(name))) => {
+                docstr.expect_only_doc_comments("`#[static_ref]`", compile_error);
+                return output::static_item(
+                    name,
+                    "&str",
+                    [TokenTree::Literal(Literal::string(&string))],
+                );
+            }
+            Some((_, Output::Named(name))) => {
+                docstr.expect_only_doc_comments("`#[named]`", compile_error);
+                return output::named_items(name, &string);
+            }
+            Some((_, Output::ChunkLines(lines))) => {
+                docstr.expect_only_doc_comments("`#[chunk_lines]`", compile_error);
+                return output::chunk_lines(&string, *lines);
+            }
+            Some((_, Output::ByteSlice(name))) => {
+                docstr
+                    .expect_only_doc_comments("`#[emit_const_str_slice_of_bytes]`", compile_error);
+                return output::byte_slice_item(name, &string);
+            }
+            None => (),
+        }
+
+        docstr.into_tokens(&string, Span::call_site(), compile_error)
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a tuple `(&'static str, usize)`
+/// where the 2nd element is the length of the string in bytes.
+///
+/// The length is computed at compile-time, so it can size arrays even where `str::len` isn't `const`.
+///
+/// ```rust
+/// use docstr::docstr_with_len;
+///
+/// const GREETING: (&str, usize) = docstr_with_len!(
+///     /// Hello
+///     /// world
+/// );
+///
+/// let buffer = [0_u8; GREETING.1];
+///
+/// assert_eq!(GREETING.0, "Hello\nworld");
+/// assert_eq!(buffer.len(), 11);
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// ("Hello\nworld", 11_usize);
+/// ```
+///
+/// It can't be passed to a macro.
+#[proc_macro]
+pub fn docstr_with_len(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_with_len!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_with_len!`", compile_error);
+        docstr.expect_no_unescape("`docstr_with_len!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_with_len!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_with_len!`", compile_error);
+        docstr.expect_no_with_location("`docstr_with_len!`", compile_error);
+
+        let string = docstr.string();
+
+        // ("foo\nbar", 7_usize)
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([
+                TokenTree::Literal(Literal::string(&string)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                TokenTree::Literal(Literal::usize_suffixed(string.len())),
+            ]),
+        ))
+        .into()
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a tuple `(&'static str, u64)`
+/// where the 2nd element is a hash of the string.
+///
+/// The hash is computed at compile-time, so it can be used as a cache key or to fingerprint
+/// an asset without hashing it on every startup.
+///
+/// ```rust
+/// use docstr::docstr_hashed;
+///
+/// const STYLE: (&str, u64) = docstr_hashed!(
+///     /// body {
+///     ///     margin: 0;
+///     /// }
+/// );
+///
+/// let url = format!("/style.{:016x}.css", STYLE.1);
+///
+/// assert_eq!(url, "/style.43b2dbf56e0da6c2.css");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// ("body {\n    margin: 0;\n}", 4878203193555527362_u64);
+/// ```
+///
+/// The hash is the 64-bit [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function)
+/// of the UTF-8 bytes of the string, so it is the same on every platform, in every build and in every
+/// version of `docstr`. It is not a cryptographic hash.
+///
+/// It can't be passed to a macro.
+#[proc_macro]
+pub fn docstr_hashed(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_hashed!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_hashed!`", compile_error);
+        docstr.expect_no_unescape("`docstr_hashed!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_hashed!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_hashed!`", compile_error);
+        docstr.expect_no_with_location("`docstr_hashed!`", compile_error);
+
+        let string = docstr.string();
+
+        // ("foo\nbar", 15132685218477139480_u64)
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([
+                TokenTree::Literal(Literal::string(&string)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                TokenTree::Literal(Literal::u64_suffixed(transform::fnv1a(&string))),
+            ]),
+        ))
+        .into()
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a `usize` literal: the number of lines in the string.
+///
+/// Blank lines are counted too, including a trailing one. It is the number of items that
+/// `str::split('\n')` returns, useful to preallocate a `Vec` before splitting.
+///
+/// ```rust
+/// use docstr::docstr_lines_count;
+///
+/// const LINES: usize = docstr_lines_count!(
+///     /// a
+///     /// b
+///     ///
+/// );
+///
+/// let lines: Vec<String> = Vec::with_capacity(LINES);
+///
+/// assert_eq!(LINES, 3);
+/// assert!(lines.capacity() >= 3);
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// 3_usize;
+/// ```
+#[proc_macro]
+pub fn docstr_lines_count(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_lines_count!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_lines_count!`", compile_error);
+        docstr.expect_no_unescape("`docstr_lines_count!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_lines_count!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_lines_count!`", compile_error);
+        docstr.expect_no_with_location("`docstr_lines_count!`", compile_error);
+
+        let lines = docstr.string().split('\n').count();
+
+        TokenTree::Literal(Literal::usize_suffixed(lines)).into()
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a slice `&'static [&'static str]` with 1 string per line.
+///
+/// All modes are applied before the string is split into lines, so the lines are the same as
+/// the lines of the string that [`docstr!`](crate::docstr) generates. For example with `#[dedent]`,
+/// the indentation which all lines have in common is removed from each line:
+///
+/// ```rust
+/// use docstr::{docstr, docstr_lines};
+///
+/// const USAGE: &[&str] = docstr_lines!(#[dedent]
+///     ///     app [OPTIONS]
+///     ///       -h  Print help
+/// );
+///
+/// assert_eq!(USAGE, ["app [OPTIONS]", "  -h  Print help"]);
+/// assert_eq!(
+///     USAGE.join("\n"),
+///     docstr!(#[dedent]
+///         ///     app [OPTIONS]
+///         ///       -h  Print help
+///     )
+/// );
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// &["app [OPTIONS]", "  -h  Print help"];
+/// ```
+///
+/// It can't be passed to a macro.
+#[proc_macro]
+pub fn docstr_lines(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_lines!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_lines!`", compile_error);
+        docstr.expect_no_unescape("`docstr_lines!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_lines!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_lines!`", compile_error);
+        docstr.expect_no_with_location("`docstr_lines!`", compile_error);
+
+        // &["foo", "bar"]
+        output::chunk_lines(&docstr.string(), 1)
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to an array `[&'static str; N]` with 1 string per paragraph.
+///
+/// Paragraphs are separated by blank lines, and keep the newlines between their own lines.
+/// Any number of blank lines separates 2 paragraphs, and blank lines at the start or end are ignored.
+///
+/// ```rust
+/// use docstr::docstr_paragraphs;
+///
+/// const HELP: [&str; 3] = docstr_paragraphs!(
+///     ///
+///     /// Usage: app [OPTIONS]
+///     ///
+///     /// Options:
+///     ///   -h  Print help
+///     ///
+///     ///
+///     /// See the manual for more.
+///     ///
+/// );
+///
+/// assert_eq!(HELP[0], "Usage: app [OPTIONS]");
+/// assert_eq!(HELP[1], "Options:\n  -h  Print help");
+/// assert_eq!(HELP[2], "See the manual for more.");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// [
+///     "Usage: app [OPTIONS]",
+///     "Options:\n  -h  Print help",
+///     "See the manual for more.",
+/// ];
+/// ```
+///
+/// It can't be passed to a macro.
+#[proc_macro]
+pub fn docstr_paragraphs(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_paragraphs!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_paragraphs!`", compile_error);
+        docstr.expect_no_unescape("`docstr_paragraphs!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_paragraphs!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_paragraphs!`", compile_error);
+        docstr.expect_no_with_location("`docstr_paragraphs!`", compile_error);
+
+        let mut paragraphs = TokenStream::new();
+        for paragraph in transform::paragraphs(&docstr.string()) {
+            paragraphs.extend([
+                TokenTree::Literal(Literal::string(&paragraph)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+
+        // ["foo\nbar", "baz"]
+        TokenTree::Group(Group::new(Delimiter::Bracket, paragraphs)).into()
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a slice `&'static [(&'static str, &'static str)]`
+/// of the `key=value` pairs on each line.
+///
+/// This is useful for lookup tables, which can be searched with a linear scan
+/// or fed to a crate like [`phf`](https://docs.rs/phf).
+///
+/// ```rust
+/// use docstr::docstr_map;
+///
+/// const STATUS: &[(&str, &str)] = docstr_map!(
+///     /// 200 = OK
+///     /// 404 = Not Found
+///     /// 500 = Internal Server Error
+/// );
+///
+/// let reason = STATUS.iter().find(|(code, _)| *code == "404").map(|(_, reason)| *reason);
+/// assert_eq!(reason, Some("Not Found"));
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// &[("200", "OK"), ("404", "Not Found"), ("500", "Internal Server Error")];
+/// ```
+///
+/// The key ends at the first `=`, so the value can contain more of them. Use `#[delimiter = ":"]` to split on
+/// something else. Keys and values are trimmed, blank lines are ignored, and lines without the delimiter are an error.
+/// The pairs are in the order they were written, see [`#[emit_static_str_map_sorted]`](crate::docstr#emit_static_str_map_sorted--name)
+/// for a sorted map.
+///
+/// It can't be passed to a macro.
+#[proc_macro]
+pub fn docstr_map(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_map!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_map!`", compile_error);
+        docstr.expect_no_unescape("`docstr_map!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_map!`", compile_error);
+        docstr.expect_no_with_location("`docstr_map!`", compile_error);
+
+        // &[("foo", "bar"), ("baz", "quux")]
+        output::map(&docstr, compile_error)
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a [`Cow<'static, str>`](std::borrow::Cow).
+///
+/// Without a macro, the string literal is [`Cow::Borrowed`](std::borrow::Cow::Borrowed).
+/// With a macro like [`format!`], its output is [`Cow::Owned`](std::borrow::Cow::Owned),
+/// so call sites which only sometimes interpolate have the same type.
+///
+/// ```rust
+/// use docstr::docstr_cow;
+/// use std::borrow::Cow;
+///
+/// fn greeting(name: Option<&str>) -> Cow<'static, str> {
+///     match name {
+///         Some(name) => docstr_cow!(format!
+///             /// Hello, {name}!
+///         ),
+///         None => docstr_cow!(
+///             /// Hello!
+///         ),
+///     }
+/// }
+///
+/// assert!(matches!(greeting(None), Cow::Borrowed("Hello!")));
+/// assert!(matches!(greeting(Some("Bob")), Cow::Owned(s) if s == "Hello, Bob!"));
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let name = "Bob";
+/// ::std::borrow::Cow::<'static, str>::Owned(format!("Hello, {name}!"));
+/// ::std::borrow::Cow::<'static, str>::Borrowed("Hello!");
+/// ```
+///
+/// With the `alloc` feature, it uses `alloc` instead of `std` like [`docstr_boxed!`](crate::docstr_boxed).
+///
+/// The macro must return a [`String`].
+#[proc_macro]
+pub fn docstr_cow(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_no_output_mode("`docstr_cow!`", compile_error);
+        docstr.expect_no_unescape("`docstr_cow!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_cow!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_cow!`", compile_error);
+
+        let variant = if docstr.macro_.is_some() {
+            "Owned"
+        } else {
+            "Borrowed"
+        };
+        let string = docstr.string();
+
+        // ::std::borrow::Cow::<'static, str>::Borrowed("foo\nbar")
+        alloc_call(
+            &format!("borrow::Cow::<'static, str>::{variant}"),
+            docstr.into_tokens(&string, Span::call_site(), compile_error),
+        )
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a byte string literal `b"..."` of type `&'static [u8; N]`.
+///
+/// ```rust
+/// use docstr::docstr_bytes;
+///
+/// const REQUEST: &[u8] = docstr_bytes!(
+///     /// GET / HTTP/1.1
+///     /// Host: example.com
+/// );
+///
+/// assert_eq!(REQUEST, b"GET / HTTP/1.1\nHost: example.com");
+/// ```
+///
+/// It can't be passed to a macro.
+///
+/// # `#[extend = buf]`
+///
+/// Appends the bytes to the `Vec<u8>` `buf` with [`Vec::extend_from_slice`], instead of producing a literal.
+///
+/// ```rust
+/// use docstr::docstr_bytes;
+///
+/// let mut buf = b"> ".to_vec();
+///
+/// docstr_bytes!(#[extend = buf]
+///     /// line 1
+///     /// line 2
+/// );
+///
+/// assert_eq!(buf, b"> line 1\nline 2");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let mut buf = Vec::new();
+/// buf.extend_from_slice(b"line 1\nline 2");
+/// ```
+///
+/// # `#[unescape]`
+///
+/// Interprets the escapes of byte string literals, so bytes which are not valid UTF-8 can be written,
+/// like test vectors: `\xNN` is the byte with hex value `NN`, and `\n`, `\r`, `\t`, `\0`, `\\`, `\'`
+/// and `\"` are the same as in `b"..."`. Any other escape is an error.
+///
+/// ```rust
+/// use docstr::docstr_bytes;
+///
+/// let bytes = docstr_bytes!(#[unescape]
+///     /// \xff\x00\\n
+/// );
+///
+/// assert_eq!(bytes, b"\xff\x00\\n");
+/// ```
+///
+/// # `#[write_all = io]`
+///
+/// Writes the bytes to `io` with [`std::io::Write::write_all`], e.g. into a socket.
+/// It expands to the call, so the [`std::io::Result`] can be handled with `?`.
+///
+/// ```rust
+/// use docstr::docstr_bytes;
+/// use std::io::Write as _;
+///
+/// fn respond(stream: &mut impl std::io::Write) -> std::io::Result<()> {
+///     docstr_bytes!(#[write_all = stream]
+///         /// HTTP/1.1 200 OK
+///         ///
+///     )?;
+///     stream.flush()
+/// }
+///
+/// let mut response = Vec::new();
+/// respond(&mut response).unwrap();
+///
+/// assert_eq!(response, b"HTTP/1.1 200 OK\n");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # use std::io::Write as _;
+/// # let mut stream = Vec::new();
+/// stream.write_all(b"HTTP/1.1 200 OK\n");
+/// ```
+#[proc_macro]
+pub fn docstr_bytes(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_bytes!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_bytes!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_bytes!`", compile_error);
+        docstr.expect_no_with_location("`docstr_bytes!`", compile_error);
+
+        let string = docstr.string();
+        let bytes = if docstr.modes.unescape.is_some() {
+            // Errors point at the line with the invalid escape
+            let mut valid = true;
+            for doc_comment in &docstr.doc_comments {
+                if let Err(message) = transform::unescape_bytes(&doc_comment.text) {
+                    compile_error(doc_comment.span, &message);
+                    valid = false;
+                }
+            }
+            if !valid {
+                return TokenStream::new();
+            }
+            transform::unescape_bytes(&string).unwrap_or_else(|message| {
+                // Lines joined with a separator can form an invalid escape
+                compile_error(Span::call_site(), &message);
+                Vec::new()
+            })
+        } else {
+            string.into_bytes()
+        };
+        let bytes = TokenTree::Literal(Literal::byte_string(&bytes));
+
+        match &docstr.modes.output {
+            // buf.extend_from_slice(b"foo\nbar")
+            Some((_, Output::Extend(target))) => {
+                output::method_call(target, "extend_from_slice", bytes)
+            }
+            // io.write_all(b"foo\nbar")
+            Some((_, Output::WriteAll(target))) => output::method_call(target, "write_all", bytes),
+            // b"foo\nbar"
+            _ => {
+                docstr.expect_no_output_mode("`docstr_bytes!`", compile_error);
+                bytes.into()
+            }
+        }
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but expands to a [`Box<str>`], which is 1 word smaller than a [`String`].
+///
+/// ```rust
+/// use docstr::docstr_boxed;
+///
+/// let boxed: Box<str> = docstr_boxed!(
+///     /// a
+///     /// b
+/// );
+///
+/// assert_eq!(&*boxed, "a\nb");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// ::std::boxed::Box::<str>::from("a\nb");
+/// ```
+///
+/// It can't be passed to a macro.
+///
+/// With the `alloc` feature, it uses `alloc` instead of `std` so it works in `#![no_std]` crates:
+///
+/// ```rust
+/// {
+///     extern crate alloc as docstr_alloc;
+///     docstr_alloc::boxed::Box::<str>::from("a\nb")
+/// };
+/// ```
+#[proc_macro]
+pub fn docstr_boxed(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_only_doc_comments("`docstr_boxed!`", compile_error);
+        docstr.expect_no_output_mode("`docstr_boxed!`", compile_error);
+        docstr.expect_no_unescape("`docstr_boxed!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_boxed!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_boxed!`", compile_error);
+        docstr.expect_no_with_location("`docstr_boxed!`", compile_error);
+
+        // ::std::boxed::Box::<str>::from("foo\nbar")
+        alloc_call(
+            "boxed::Box::<str>::from",
+            TokenTree::Literal(Literal::string(&docstr.string())).into(),
+        )
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but the generated string literal has the span of the doc comments
+/// instead of the span of the macro invocation.
+///
+/// This is useful when the string is passed to another macro which reports errors
+/// about its contents, such as a SQL checker, so the errors point at the doc comments:
+///
+/// ```rust
+/// use docstr::docstr_spanned;
+///
+/// let query: &str = docstr_spanned!(
+///     /// SELECT name
+///     /// FROM users
+/// );
+///
+/// assert_eq!(query, "SELECT name\nFROM users");
+/// ```
+///
+/// The whole literal has the span of the 1st doc comment, because joining spans is not available on stable Rust.
+#[proc_macro]
+pub fn docstr_spanned(input: TokenStream) -> TokenStream {
+    expand(input, |docstr, compile_error| {
+        docstr.expect_no_output_mode("`docstr_spanned!`", compile_error);
+        docstr.expect_no_unescape("`docstr_spanned!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_spanned!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_spanned!`", compile_error);
+
+        let span = docstr
+            .doc_comments
+            .first()
+            .map_or_else(Span::call_site, |doc_comment| doc_comment.span);
+        let string = docstr.string();
+
+        docstr.into_tokens(&string, span, compile_error)
+    })
+}
+
+/// Like [`docstr!`](crate::docstr), but passes the same string to several macros, separated by `,`.
+///
+/// ```rust
+/// use docstr::docstr_tee;
+///
+/// let name = "Bob";
+/// let greeting: String = docstr_tee!(eprintln!, format!
+///     /// Hello, {name}!
+/// );
+///
+/// assert_eq!(greeting, "Hello, Bob!");
+/// ```
+///
+/// Expands to this:
+///
+/// ```rust
+/// # let name = "Bob";
+/// {
+///     eprintln!("Hello, {name}!");
+///     format!("Hello, {name}!")
+/// }
+/// # ;
+/// ```
+///
+/// The macros are called in the order they are written, and the block evaluates to what the last one returns.
+/// Each macro only receives the string, so there can't be any tokens after the doc comments.
+/// Arguments would be evaluated once for each macro, so capture variables like `{name}` instead.
+#[proc_macro]
+pub fn docstr_tee(input: TokenStream) -> TokenStream {
+    expand(input, |mut docstr, compile_error| {
+        docstr.expect_no_output_mode("`docstr_tee!`", compile_error);
+        docstr.expect_no_unescape("`docstr_tee!`", compile_error);
+        docstr.expect_no_cfg_lines("`docstr_tee!`", compile_error);
+        docstr.expect_no_delimiter("`docstr_tee!`", compile_error);
+
+        if docstr.modes.into.is_some() || docstr.modes.at_placeholder {
+            compile_error(
+                Span::call_site(),
+                "`#[into]` and `#[at_placeholder]` are not supported by `docstr_tee!`",
+            );
+        }
+        if !docstr.after.is_empty() || docstr.middle.is_some() {
+            compile_error(
+                Span::call_site(),
+                "`docstr_tee!` only passes the string to each macro, it does not accept any tokens after the doc comments",
+            );
+            return TokenStream::new();
+        }
+
+        // eprintln!, format!
+        // ^^^^^^^^^  ^^^^^^^
+        let mut macros = Vec::from_iter(docstr.macro_.take());
+        let mut errors = Vec::new();
+        let mut tokens = std::mem::take(&mut docstr.before).into_iter().peekable();
+        while let Some(comma) = tokens.next() {
+            if !matches!(&comma, TokenTree::Punct(comma) if *comma == ',') {
+                compile_error(comma.span(), "expected `,` before the next macro path");
+                return TokenStream::new();
+            }
+            // The last `,` before the doc comments is optional
+            if tokens.peek().is_none() {
+                break;
+            }
+
+            match Docstr::parse_macro_path(&mut tokens, false, &mut |error| errors.push(error)) {
+                Some((path, _)) => macros.push(path),
+                None => return CompileError::deduplicated(errors),
+            }
+        }
+        if macros.is_empty() {
+            compile_error(
+                Span::call_site(),
+                "`docstr_tee!` expects paths to macros like `eprintln!, format!` before the doc comments",
+            );
+            return TokenStream::new();
+        }
+
+        // "foo\nbar"
+        let string = docstr.string();
+        let literal = docstr.into_tokens(&string, Span::call_site(), compile_error);
+
+        // {
+        //     eprintln!("foo\nbar");
+        //     format!("foo\nbar")
+        // }
+        let mut block = TokenStream::new();
+        let last = macros.len() - 1;
+        for (index, path) in macros.into_iter().enumerate() {
+            block.extend(path);
+            block.extend([TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                literal.clone(),
+            ))]);
+            if index != last {
+                block.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+            }
+        }
+
+        TokenTree::Group(Group::new(Delimiter::Brace, block)).into()
+    })
+}
+
+/// Parses the input of `docstr!` or one of its variants, then generates the output with `f`
+///
+/// If we encounter any errors, we collect them and report them all at once
+/// instead of the output
+fn expand(
+    input: TokenStream,
+    f: impl FnOnce(Docstr, &mut dyn FnMut(Span, &str)) -> TokenStream,
+) -> TokenStream {
+    // compile_error!("you have done horrible things!")
+    let mut compile_errors = Vec::new();
+
+    let docstr = Docstr::parse(input, &mut |compile_error| {
+        compile_errors.push(compile_error);
+    });
+
+    // Generating the output makes no sense if we couldn't even parse the input
+    if !compile_errors.is_empty() {
+        return CompileError::deduplicated(compile_errors);
+    }
+
+    let output = f(docstr, &mut |span, message| {
+        compile_errors.push(CompileError::new(span, message));
+    });
+
+    if !compile_errors.is_empty() {
+        return CompileError::deduplicated(compile_errors);
+    }
+
+    output
+}
+
+/// Input of `docstr!`
+///
+/// ```ignore
+/// docstr!(#[mode] macro_! before
+///     /// doc comments
+///     after
+/// )
+/// ```
+struct Docstr {
+    /// Modes which change how the input is processed, see [`Modes`]
+    modes: Modes,
+    /// Path to the macro that we send tokens to.
+    ///
+    /// If this is `None`, this macro produces a string literal
+    macro_: Option<TokenStream>,
+    /// Tokens BEFORE the doc comments, which are appended
+    /// directly to the `macro_`
+    before: TokenStream,
+    /// Each doc comment `/// ...`
+    doc_comments: Vec<DocComment>,
+    /// Tokens AFTER the doc comments, which are appended
+    /// directly to the `macro_`
+    after: TokenStream,
+    /// Tokens BETWEEN the 2 regions of doc comments, if there is a 2nd one.
+    /// They are passed between the 2 strings
+    middle: Option<TokenStream>,
+}
+
+impl Docstr {
+    /// Parse the input, reporting any errors with `report`
+    fn parse(input: TokenStream, report: &mut impl FnMut(CompileError)) -> Self {
+        let mut input = input.into_iter().peekable();
+
+        // #[deflate = NAME]
+        // ^^^^^^^^^^^^^^^^^ modes come before everything else
+        let modes = Modes::parse(&mut input, &mut |span, message| {
+            report(CompileError::new(span, message));
+        });
+
+        // std::format!
+        // ^^^^^^^^^^^^ first and last token of the path, if it is valid
+        let mut macro_span = None;
+
+        // Path to the macro that we send tokens to.
+        //
+        // If this is `None`, this macro produces a string literal
+        let macro_ = match input.peek() {
+            // Modes which generate something other than a string never pass
+            // it to a macro, so any tokens here are the ones before doc comments
+            _ if modes.output.is_some() => None,
+            Some(TokenTree::Punct(punct)) if *punct == '#' => {
+                // No macro, this will directly produce a string literal
+                None
+            }
+            // Ok, this is a path to a macro.
+            //
+            // on the first compile error we stop trying to process the path because it won't
+            // make any sense after that
+            Some(_) => Some(match Self::parse_macro_path(&mut input, true, report) {
+                Some((macro_, span)) => {
+                    macro_span = Some(span);
+                    macro_
+                }
+                None => TokenStream::new(),
+            }),
+            // Macro input is totally empty - just expand to an empty string
+            None => None,
+        };
+
+        // The rest of the errors only point at a single span
+        let compile_error = &mut |span, message: &str| report(CompileError::new(span, message));
+
+        // Tokens BEFORE the doc comments, which are appended
+        // directly to the `macro_` we just got
+        let mut before = TokenStream::new();
+
+        // Contents of the doc comments which we collect
+        //
+        // /// foo
+        // /// bar
+        //
+        // Expands to:
+        //
+        // #[doc = "foo"]
+        // #[doc = "bar"]
+        //
+        // Which we collect to:
+        //
+        // ["foo", "bar"]
+        let mut doc_comments = Vec::new();
+
+        // Tokens AFTER the doc comments, which are appended
+        // directly to the `macr` we just got
+        let mut after = TokenStream::new();
+
+        // Tokens BETWEEN the 2 regions of doc comments, if there is a 2nd one
+        let mut middle = None;
+
+        // State machine corresponding to our current progress in the macro
+        let mut doc_comment_progress = DocCommentProgress::NotReached;
+
+        // `#[cfg(...)]` attributes for the next doc comment, and the span of the last one
+        let mut cfg = TokenStream::new();
+        let mut cfg_span = None;
+
+        // Let's collect all of the doc comments into a Vec<String> where each
+        // String corresponds to the doc comment
+        while let Some(tt) = input.next() {
+            // Whether this can be the 1st doc comment, instead of one after a doc comment
+            let is_first = doc_comment_progress == DocCommentProgress::NotReached;
+
+            // #[doc = "..."]
+            // ^
+            let doc_comment_start_span = match tt {
+                // this token is passed verbatim to the macro at the end,
+                // after the doc comments
+                tt if doc_comment_progress == DocCommentProgress::Finished => {
+                    after.extend([tt]);
+                    continue;
+                }
+                // docstr!(concat! /// foo
+                //     env!("BAR")
+                //     /// baz
+                //     ^^^^^^^ start of the 2nd region of doc comments
+                // )
+                TokenTree::Punct(punct)
+                    if punct == '#'
+                        && doc_comment_progress == DocCommentProgress::Between
+                        && matches!(input.peek(), Some(TokenTree::Group(brackets)) if is_doc_attribute(brackets)) =>
+                {
+                    doc_comment_progress = DocCommentProgress::InsideSuffix;
+                    middle = Some(std::mem::take(&mut after));
+                    punct.span()
+                }
+                // these tokens are passed between the 2 regions of doc comments,
+                // or after the doc comments if there is no 2nd region
+                tt if doc_comment_progress == DocCommentProgress::Between => {
+                    after.extend([tt]);
+                    continue;
+                }
+                // docstr!(my_macro! #x /// ...)
+                //                   ^ this `#` doesn't start an attribute, so it is passed
+                //                     verbatim to the macro, before the doc comments
+                TokenTree::Punct(punct)
+                    if punct == '#'
+                        && doc_comment_progress == DocCommentProgress::NotReached
+                        && macro_.is_some()
+                        && !matches!(
+                            input.peek(),
+                            Some(TokenTree::Group(brackets)) if brackets.delimiter() == Delimiter::Bracket
+                        )
+                        && !matches!(input.peek(), Some(TokenTree::Punct(punct)) if *punct == '!') =>
+                {
+                    before.extend([TokenTree::Punct(punct)]);
+                    continue;
+                }
+                // start of doc comment
+                TokenTree::Punct(punct) if punct == '#' => {
+                    match doc_comment_progress {
+                        DocCommentProgress::NotReached => {
+                            doc_comment_progress = DocCommentProgress::Inside;
+                        }
+                        DocCommentProgress::Inside | DocCommentProgress::InsideSuffix => {
+                            // ok
+                        }
+                        DocCommentProgress::Between | DocCommentProgress::Finished => {
+                            unreachable!("if it's finished we would `continue` in an earlier arm")
+                        }
+                    }
+                    match input.peek() {
+                        Some(TokenTree::Punct(punct)) if *punct == '!' => {
+                            compile_error(
+                                punct.span(),
+                                "Inner doc comments `//! ...` are not supported. Please use `/// ...`",
+                            );
+                            // eat '!'
+                            input.next();
+                        }
+                        _ => (),
+                    }
+                    punct.span()
+                }
+                // this token is passed verbatim to the macro at the beginning,
+                // before the doc comments
+                tt if doc_comment_progress == DocCommentProgress::NotReached => {
+                    before.extend([tt]);
+                    continue;
+                }
+                _ => {
+                    unreachable!(
+                        "when the next token is not `#` progress is `Between` or `Finished`"
+                    )
+                }
+            };
+
+            // #[doc = "..."]
+            //  ^^^^^^^^^^^^^
+            //
+            // /// foo
+            // #bar
+            //  ^^^ a `#` after a doc comment always starts the next one
+            let doc_comment_square_brackets = match input.next() {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
+                Some(tt) if is_first => {
+                    compile_error(tt.span(), "expected `[...]`");
+                    continue;
+                }
+                None if is_first => {
+                    compile_error(
+                        doc_comment_start_span,
+                        "expected `#` to be followed by `[...]`",
+                    );
+                    continue;
+                }
+                tt => {
+                    compile_error(
+                        tt.map_or(doc_comment_start_span, |tt| tt.span()),
+                        concat!(
+                            "expected `[...]` after `#`, because a `#` after a doc comment ",
+                            "starts another doc comment\n\nhelp: to pass the `#` to the macro ",
+                            "instead, end the doc comments with `=>`: `/// ... => #...`"
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            // Check if there is a doc comment after this one
+            //
+            // #[doc = "..."]            #[doc = "..."]
+            // ^^^^^^^^^^^^^^ current    ^ next?
+            //
+            // #[doc = "..."] => #tag
+            //                ^^ explicit end of the doc comments, so even a `#` after it
+            //                   is passed to the macro instead of starting a doc comment
+            let is_terminator = matches!(
+                input.peek(),
+                Some(TokenTree::Punct(eq)) if *eq == '=' && eq.spacing() == Spacing::Joint
+            ) && {
+                let mut lookahead = input.clone();
+                lookahead.next();
+                matches!(lookahead.next(), Some(TokenTree::Punct(gt)) if gt == '>')
+            };
+            match input.peek() {
+                Some(TokenTree::Punct(punct)) if *punct == '#' => {
+                    // Yes, there is. Continue doc comment
+                }
+                _ if is_terminator => {
+                    // eat `=>`
+                    input.next();
+                    input.next();
+                    doc_comment_progress = DocCommentProgress::Finished;
+                }
+                _ if doc_comment_progress == DocCommentProgress::Inside => {
+                    // The next token is not `#` so the 1st region of doc comments ended,
+                    // but there can still be a 2nd one
+                    doc_comment_progress = DocCommentProgress::Between;
+                }
+                _ => {
+                    // The next token is not `#` so there are no more doc comments
+                    doc_comment_progress = DocCommentProgress::Finished;
+                }
+            }
+
+            // #[cfg(unix)]
+            // ^^^^^^^^^^^^ the doc comment after it is only included when the cfg holds
+            if is_cfg_attribute(&doc_comment_square_brackets) {
+                cfg_span = Some(doc_comment_square_brackets.span());
+                cfg.extend([
+                    TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+                    TokenTree::Group(doc_comment_square_brackets),
+                ]);
+                continue;
+            }
+
+            // #[doc = "..."]
+            //         ^^^^^
+            let Some(literal) = parse_doc_attribute(&doc_comment_square_brackets, compile_error)
+            else {
+                continue;
+            };
+
+            // Reached contents of the doc comment
+            //
+            // let's remove leading space
+            //
+            // /// foo bar
+            //
+            // this expands to:
+            //
+            // #[doc = " foo bar"]
+            //          ^ remove this space from the actual output
+            //
+            // We usually always have a space after the comment token,
+            // since it looks good. And e.g. Rustdoc ignores it as well.
+            //
+            // With `#[strip = N]`, remove up to `N` spaces instead
+            let strip = modes.strip.unwrap_or(1);
+            let spaces = literal
+                .bytes()
+                .take(strip)
+                .take_while(|&b| b == b' ')
+                .count();
+            let literal = &literal[spaces..];
+
+            doc_comments.push(DocComment {
+                text: literal.to_string(),
+                span: doc_comment_start_span,
+                cfg: std::mem::take(&mut cfg),
+                suffix: middle.is_some(),
+            });
+        }
+
+        // #[cfg(unix)]
+        // ^^^^^^^^^^^^ there is no doc comment after it
+        if let Some(span) = cfg_span.filter(|_| !cfg.is_empty()) {
+            compile_error(span, "expected a doc comment `/// ...` after `#[cfg(...)]`");
+        }
+
+        // #[lines = 1..3]
+        if let Some(range) = &modes.lines {
+            let end = range.end.unwrap_or(doc_comments.len());
+            if end > doc_comments.len() || range.start > doc_comments.len() {
+                compile_error(
+                    range.span,
+                    &format!(
+                        "the range is out of bounds, there {}",
+                        match doc_comments.len() {
+                            1 => "is only 1 line".to_string(),
+                            lines => format!("are only {lines} lines"),
+                        }
+                    ),
+                );
+            } else {
+                doc_comments.truncate(end);
+                doc_comments.drain(..range.start);
+            }
+        }
+
+        if doc_comments.is_empty() && !modes.allow_empty {
+            match macro_span {
+                // docstr!(format!)
+                //         ^^^^^^^ the doc comments were most likely forgotten
+                Some((start, end)) => report(CompileError::spanning(
+                    start,
+                    end,
+                    format!(
+                        "expected documentation comments `/// ...` after `{}`",
+                        macro_.as_ref().map(path_to_string).unwrap_or_default()
+                    ),
+                )),
+                None => report(CompileError::new(
+                    Span::call_site(),
+                    "expected at least 1 documentation comment `/// ...`",
+                )),
+            }
+        }
+
+        // The errors from here on only point at a single span
+        let compile_error = &mut |span, message: &str| report(CompileError::new(span, message));
+
+        // Apply the modes which transform each line
+        for doc_comment in &mut doc_comments {
+            match modes.normalize_newlines {
+                Some(NormalizeNewlines::Trailing) => {
+                    let len = doc_comment.text.trim_end_matches('\r').len();
+                    doc_comment.text.truncate(len);
+                }
+                Some(NormalizeNewlines::All) => doc_comment.text.retain(|ch| ch != '\r'),
+                None => (),
+            }
+            if modes.strip_ansi {
+                doc_comment.text = transform::strip_ansi(&doc_comment.text);
+            }
+            // /// trailing spaces   \
+            //                       ^ removed, the spaces are kept
+            //
+            // `\\` at the end becomes `\`
+            if modes.explicit_trailing && doc_comment.text.ends_with('\\') {
+                doc_comment.text.pop();
+            }
+            if let Some(tab_width) = modes.tabs {
+                doc_comment.text = transform::expand_tabs(&doc_comment.text, tab_width);
+            }
+            if let Some(substitutions) = &modes.subst {
+                let (text, unknown) = transform::substitute(&doc_comment.text, substitutions);
+                // With a macro like `format!`, the rest are its placeholders
+                if macro_.is_none() {
+                    for name in unknown {
+                        compile_error(
+                            doc_comment.span,
+                            &format!("`{{{name}}}` is not defined by `#[subst]`"),
+                        );
+                    }
+                }
+                doc_comment.text = text;
+            }
+            if modes.strip_doc_links {
+                doc_comment.text = transform::strip_doc_links(&doc_comment.text);
+            }
+            if modes.printf_placeholders {
+                match transform::printf_to_format(&doc_comment.text) {
+                    Ok(text) => doc_comment.text = text,
+                    Err(placeholder) => compile_error(
+                        doc_comment.span,
+                        &format!("unsupported printf placeholder `{placeholder}`"),
+                    ),
+                }
+            }
+            if modes.brace_unescape && macro_.is_none() {
+                match transform::unescape_braces(&doc_comment.text) {
+                    Ok(text) => doc_comment.text = text,
+                    Err(brace) => compile_error(
+                        doc_comment.span,
+                        &format!("`{brace}` must be escaped as `{brace}{brace}` with `#[brace_unescape]`"),
+                    ),
+                }
+            }
+        }
+
+        // The macro unescapes braces itself, so they would be unescaped twice
+        if modes.brace_unescape && macro_.is_some() {
+            compile_error(
+                Span::call_site(),
+                "`#[brace_unescape]` can only be used without a macro, which unescapes `{{` and `}}` itself",
+            );
+        }
+
+        if let Some(marker) = &modes.strip_comments {
+            doc_comments.retain(|doc_comment| !doc_comment.text.trim_start().starts_with(marker));
+        }
+        if modes.trim_blank_lines {
+            let is_blank = |doc_comment: &DocComment| doc_comment.text.trim().is_empty();
+            let trailing = doc_comments
+                .iter()
+                .rev()
+                .take_while(|d| is_blank(d))
+                .count();
+            doc_comments.truncate(doc_comments.len() - trailing);
+            let leading = doc_comments.iter().take_while(|d| is_blank(d)).count();
+            doc_comments.drain(..leading);
+        }
+        if modes.dedent_first {
+            transform::dedent_first(
+                doc_comments
+                    .iter_mut()
+                    .map(|doc_comment| &mut doc_comment.text),
+            );
+        }
+        if modes.dedent {
+            let (least_indented, inconsistent) = transform::dedent(
+                doc_comments
+                    .iter_mut()
+                    .map(|doc_comment| &mut doc_comment.text),
+            );
+            for index in inconsistent {
+                compile_error(
+                    doc_comments[index].span,
+                    &format!(
+                        "indentation of this line mixes tabs and spaces differently than line {}",
+                        least_indented + 1
+                    ),
+                );
+            }
+        }
+
+        // /// a
+        // /// b
+        // ///
+        //
+        // becomes "b\na\n", so the string still ends with the blank line
+        if modes.reverse_lines {
+            let trailing_blank = doc_comments
+                .iter()
+                .rev()
+                .take_while(|doc_comment| doc_comment.text.is_empty())
+                .count();
+            let len = doc_comments.len() - trailing_blank;
+            doc_comments[..len].reverse();
+        }
 
-                        last_is_ident = true;
-                        macro_.extend([TokenTree::Ident(ident)]);
-                    }
-                    Some(TokenTree::Punct(comma)) if comma == ',' => {
-                        compile_error(
-                            comma.span(),
-                            &format!("replace with `!` to pass the macro: `{macro_}!`",),
-                        );
-                        macro_ = TokenStream::new();
-                        break;
-                    }
-                    _ => {
-                        let span = tt.map(|tt| tt.span()).unwrap_or_else(|| {
-                            macro_
-                                .clone()
-                                .into_iter()
-                                .last()
-                                .map(|last| last.span())
-                                .unwrap_or_else(Span::call_site)
-                        });
-                        compile_error(
+        if modes.toc {
+            let toc = transform::table_of_contents(
+                doc_comments
+                    .iter()
+                    .map(|doc_comment| doc_comment.text.as_str()),
+            );
+            if !toc.is_empty() {
+                let span = doc_comments[0].span;
+                doc_comments.splice(
+                    0..0,
+                    toc.into_iter()
+                        .chain([String::new()])
+                        .map(|text| DocComment {
+                            text,
                             span,
-                            concat!(
-                                "expected path ",
-                                "to macro like: `std::format!`\n\nnote: ",
-                                "macro path is optional and can be omitted ",
-                                "to produce a `&'static str`"
-                            ),
-                        );
-                        macro_ = TokenStream::new();
-                        break;
+                            cfg: TokenStream::new(),
+                            suffix: false,
+                        }),
+                );
+            }
+        }
+
+        // Wrapping turns 1 line into several, each keeps the span of the original line
+        if let Some(width) = modes.wrap {
+            doc_comments = doc_comments
+                .into_iter()
+                .flat_map(|doc_comment| {
+                    transform::wrap(
+                        &doc_comment.text,
+                        width,
+                        modes.hang.unwrap_or(0),
+                        modes.wrap_preserve_indent,
+                    )
+                    .into_iter()
+                    .map(move |text| DocComment {
+                        text,
+                        span: doc_comment.span,
+                        cfg: doc_comment.cfg.clone(),
+                        suffix: doc_comment.suffix,
+                    })
+                })
+                .collect();
+        }
+
+        let mut number = modes.number_from.unwrap_or(1);
+        for doc_comment in &mut doc_comments {
+            // #[number_format = "{n}| "]
+            if modes.number && !(modes.number_skip_blank && doc_comment.text.is_empty()) {
+                let format = modes.number_format.as_deref().unwrap_or("{n}: ");
+                let prefix = format.replace("{n}", &number.to_string());
+                doc_comment.text.insert_str(0, &prefix);
+                number += 1;
+            }
+            if modes.escape_for_makefile {
+                doc_comment.text = doc_comment.text.replace('$', "$$");
+            }
+            if modes.line_hash_comments {
+                let crc = transform::crc32(doc_comment.text.as_bytes());
+                doc_comment.text.push_str(&format!(" # crc:{crc:08x}"));
+            }
+            match &modes.diff {
+                Some(Diff::Markers(prefix)) => {
+                    if !doc_comment.text.starts_with(['+', '-', ' ']) {
+                        doc_comment.text.insert_str(0, prefix);
                     }
                 }
+                Some(Diff::All(marker)) => doc_comment.text.insert(0, *marker),
+                None => (),
             }
+        }
 
-            Some(macro_)
+        // Validate the lines after they have been transformed
+        for (index, doc_comment) in doc_comments.iter().enumerate() {
+            if modes.validate_no_trailing_whitespace
+                && doc_comment.text.trim_end() != doc_comment.text
+            {
+                compile_error(
+                    doc_comment.span,
+                    &format!("line {} has trailing whitespace", index + 1),
+                );
+            }
         }
-        // Macro input is totally empty - just expand to an empty string
-        None => {
-            return CompileError::new(
-                Span::call_site(),
-                "expected at least 1 documentation comment `/// ...`",
-            )
-            .into_iter()
-            .collect()
+        if modes.validate_json {
+            let json = doc_comments
+                .iter()
+                .map(|doc_comment| doc_comment.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if let Some((line, error)) = transform::invalid_json(&json) {
+                // A line with the error, or the last one for errors at the end of the input
+                let span = doc_comments
+                    .get(line)
+                    .or(doc_comments.last())
+                    .map_or_else(Span::call_site, |doc_comment| doc_comment.span);
+                compile_error(span, &format!("invalid JSON: {error}"));
+            }
         }
-    };
+        if modes.check_braces {
+            let unbalanced = transform::unbalanced_braces(
+                doc_comments
+                    .iter()
+                    .map(|doc_comment| doc_comment.text.as_str()),
+            );
+            for (index, brace) in unbalanced {
+                let message = if brace == '{' {
+                    "`{` is never closed, use `{{` for a literal `{`"
+                } else {
+                    "`}` is never opened, use `}}` for a literal `}`"
+                };
+                compile_error(doc_comments[index].span, message);
+            }
+        }
+
+        Self {
+            modes,
+            macro_,
+            before,
+            doc_comments,
+            after,
+            middle,
+        }
+    }
 
-    // Tokens BEFORE the doc comments, which are appended
-    // directly to the `macro_` we just got
-    let mut before = TokenStream::new();
+    /// Parse the path to a macro up to and including its `!`, like `std::format!`
+    ///
+    /// Returns the path and the span of its first and last token, or `None` if it is invalid.
+    /// `optional` is whether the path can be removed to produce a `&'static str`,
+    /// which is mentioned in the errors
+    fn parse_macro_path(
+        input: &mut Peekable<token_stream::IntoIter>,
+        optional: bool,
+        report: &mut impl FnMut(CompileError),
+    ) -> Option<(TokenStream, (Span, Span))> {
+        let mut macro_ = TokenStream::new();
+        // for better error messages
+        let mut last_ident = None;
+        let first_span = input.peek().map_or_else(Span::call_site, |tt| tt.span());
 
-    // Contents of the doc comments which we collect
-    //
-    // /// foo
-    // /// bar
-    //
-    // Expands to:
-    //
-    // #[doc = "foo"]
-    // #[doc = "bar"]
-    //
-    // Which we collect to:
-    //
-    // ["foo", "bar"]
-    let mut doc_comments = Vec::new();
-
-    // Tokens AFTER the doc comments, which are appended
-    // directly to the `macr` we just got
-    let mut after = TokenStream::new();
-
-    // State machine corresponding to our current progress in the macro
-    let mut doc_comment_progress = DocCommentProgress::NotReached;
-
-    // Let's collect all of the doc comments into a Vec<String> where each
-    // String corresponds to the doc comment
-    while let Some(tt) = input.next() {
-        // #[doc = "..."]
-        // ^
-        let doc_comment_start_span = match tt {
-            // this token is passed verbatim to the macro at the end,
-            // after the doc comments
-            tt if doc_comment_progress == DocCommentProgress::Finished => {
-                after.extend([tt]);
-                continue;
+        loop {
+            // docstr!(String /// x)
+            //                ^ doc comments start, but we never saw the `!`
+            //
+            // This is only peeked, so the doc comments are still parsed
+            if matches!(input.peek(), Some(TokenTree::Punct(hash)) if *hash == '#')
+                || input.peek().is_none()
+            {
+                if let Some(last) = macro_.clone().into_iter().last() {
+                    let path = path_to_string(&macro_);
+                    let help = if optional {
+                        format!("did you mean one of:\n- `{path}!` to pass the string to a macro\n- remove `{path}` to produce a `&'static str`")
+                    } else {
+                        format!("help: did you mean `{path}!`?")
+                    };
+                    report(CompileError::spanning(
+                        first_span,
+                        last.span(),
+                        format!("expected `!` after the path to the macro\n\n{help}"),
+                    ));
+                    return None;
+                }
             }
-            // start of doc comment
-            TokenTree::Punct(punct) if punct == '#' => {
-                match doc_comment_progress {
-                    DocCommentProgress::NotReached => {
-                        doc_comment_progress = DocCommentProgress::Inside;
-                    }
-                    DocCommentProgress::Inside => {
-                        // ok
-                    }
-                    DocCommentProgress::Finished => {
-                        unreachable!("if it's finished we would `continue` in an earlier arm")
-                    }
+
+            let tt = input.next();
+            match tt {
+                // docstr!(! /// x)
+                //         ^ there is no path before the `!`
+                Some(TokenTree::Punct(exclamation)) if exclamation == '!' && macro_.is_empty() => {
+                    report(CompileError::new(
+                        exclamation.span(),
+                        "expected path to macro before the `!`, like: `std::format!`",
+                    ));
+                    return None;
                 }
-                match input.peek() {
-                    Some(TokenTree::Punct(punct)) if *punct == '!' => {
-                        compile_error(
-                            punct.span(),
-                            "Inner doc comments `//! ...` are not supported. Please use `/// ...`",
-                        );
-                        // eat '!'
-                        input.next();
+                // std::format!
+                //            ^
+                Some(TokenTree::Punct(exclamation)) if exclamation == '!' => {
+                    let span = (first_span, exclamation.span());
+                    macro_.extend([TokenTree::Punct(exclamation)]);
+                    // end of the macro
+                    return Some((macro_, span));
+                }
+                // std::format!
+                //    ^
+                //     ^
+                Some(TokenTree::Punct(colon)) if colon == ':' => {
+                    last_ident = None;
+                    macro_.extend([TokenTree::Punct(colon)]);
+                }
+                // std::format!
+                // ^^^
+                //      ^^^^^^
+                Some(TokenTree::Ident(ident)) => {
+                    // std::format foo
+                    //      ^^^^^^^^^^ both identifiers are underlined
+                    if let Some(last_ident) = last_ident {
+                        let path = path_to_string(&macro_);
+                        report(CompileError::spanning(last_ident, ident.span(), format!("2 identifiers in a row is not a valid macro path\n\ndid you mean one of:\n- `{path}::{ident}`\n- `{path}! {ident}`")));
+                        return None;
                     }
-                    _ => (),
+
+                    last_ident = Some(ident.span());
+                    macro_.extend([TokenTree::Ident(ident)]);
                 }
-                punct.span()
-            }
-            // this token is passed verbatim to the macro at the beginning,
-            // before the doc comments
-            tt if doc_comment_progress == DocCommentProgress::NotReached => {
-                // Comma before '#' is optional
-                //
-                // docstr!(writeln! w,
-                //                   ^ this comma can be omitted
-                //     #[doc = "..."]
-                //     ^ next token
-                // )
-                let insert_comma = match input.peek() {
-                    Some(TokenTree::Punct(next)) => match &tt {
-                        TokenTree::Punct(current) if *current == ',' && *next == '#' => false,
-                        _ if *next == '#' => true,
-                        _ => false,
-                    },
-                    _ => false,
-                };
+                Some(TokenTree::Punct(comma)) if comma == ',' => {
+                    report(CompileError::new(
+                        comma.span(),
+                        format!(
+                            "replace with `!` to pass the macro: `{}!`",
+                            path_to_string(&macro_)
+                        ),
+                    ));
+                    return None;
+                }
+                _ => {
+                    let span = tt.map(|tt| tt.span()).unwrap_or_else(|| {
+                        macro_
+                            .clone()
+                            .into_iter()
+                            .last()
+                            .map(|last| last.span())
+                            .unwrap_or_else(Span::call_site)
+                    });
+                    let note = if optional {
+                        "\n\nnote: macro path is optional and can be omitted to produce a `&'static str`"
+                    } else {
+                        ""
+                    };
+                    report(CompileError::new(
+                        span,
+                        format!("expected path to macro like: `std::format!`{note}"),
+                    ));
+                    return None;
+                }
+            }
+        }
+    }
 
-                before.extend([tt]);
+    /// The fully constructed string that we output
+    ///
+    /// ```ignore
+    /// docstr!(
+    ///     /// foo
+    ///     /// bar
+    /// )
+    /// ```
+    ///
+    /// becomes this:
+    ///
+    /// ```ignore
+    /// "foo\nbar"
+    /// ```
+    fn string(&self) -> String {
+        let separator = self.modes.join.as_deref().unwrap_or("\n");
+        let mut string = self.join_lines(false);
 
-                if insert_comma {
-                    before.extend([TokenTree::Punct(Punct::new(',', Spacing::Joint))]);
-                }
+        // Apply the modes which transform the whole string
+        if let Some(times) = self.modes.repeat {
+            string = vec![string; times].join(separator);
+        }
+        match self.modes.case {
+            Some(Case::Upper) => string = string.to_uppercase(),
+            Some(Case::Lower) => string = string.to_lowercase(),
+            None => (),
+        }
+        if let Some(normalization) = self.modes.normalization {
+            string = transform::normalize(&string, normalization);
+        }
 
-                continue;
-            }
-            _ => {
-                unreachable!("when the next token is not `#` progress is `Finished`")
+        if self.modes.leading_newline && !string.starts_with('\n') {
+            string.insert(0, '\n');
+        }
+        if self.modes.strip_final_newline && string.ends_with('\n') {
+            string.pop();
+        }
+        if let Some(interpreter) = &self.modes.ensure_shebang {
+            if !string.starts_with("#!") {
+                string.insert_str(0, &format!("#!{interpreter}\n"));
             }
+        }
+        if let Some((open, close)) = &self.modes.wrap_in {
+            string.insert_str(0, open);
+            string.push_str(close);
+        }
+        if self.modes.hex_dump {
+            string = transform::hex_dump(string.as_bytes());
+        }
+
+        string
+    }
+
+    /// The string of the 2nd region of doc comments, if there is one
+    ///
+    /// Modes which change the whole string can't be used with 2 regions, except for the case and normalization
+    fn suffix_string(&self) -> Option<String> {
+        self.middle.as_ref()?;
+
+        let mut string = self.join_lines(true);
+        match self.modes.case {
+            Some(Case::Upper) => string = string.to_uppercase(),
+            Some(Case::Lower) => string = string.to_lowercase(),
+            None => (),
+        }
+        if let Some(normalization) = self.modes.normalization {
+            string = transform::normalize(&string, normalization);
+        }
+        Some(string)
+    }
+
+    /// Join the lines of the 1st or the 2nd region of doc comments
+    fn join_lines(&self, suffix: bool) -> String {
+        self.doc_comments
+            .iter()
+            .filter(|doc_comment| doc_comment.suffix == suffix)
+            .map(|doc_comment| doc_comment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(self.modes.join.as_deref().unwrap_or("\n"))
+    }
+
+    /// The modes which change the whole string after the lines are joined
+    fn whole_string_modes(&self) -> impl Iterator<Item = &'static str> {
+        let modes = &self.modes;
+        [
+            (modes.repeat.is_some(), "repeat"),
+            (modes.leading_newline, "leading_newline"),
+            (modes.strip_final_newline, "strip_final_newline"),
+            (modes.ensure_shebang.is_some(), "ensure_shebang"),
+            (modes.wrap_in.is_some(), "wrap_in"),
+            (modes.hex_dump, "emit_raw_bytes_hex_dump"),
+        ]
+        .into_iter()
+        .filter_map(|(used, mode)| used.then_some(mode))
+    }
+
+    /// Generates the string literal with the given `span`, or passes it to the macro if there is one
+    ///
+    /// ```ignore
+    /// docstr!(format! hello
+    ///     /// foo bar
+    ///     a, b
+    /// )
+    /// ```
+    ///
+    /// becomes this:
+    ///
+    /// ```ignore
+    /// format!(hello, "foo bar", a, b)
+    /// ```
+    fn into_tokens(
+        self,
+        string: &str,
+        span: Span,
+        compile_error: &mut dyn FnMut(Span, &str),
+    ) -> TokenStream {
+        let mut literal = if self.modes.raw {
+            output::raw_string(string).unwrap_or_else(|| Literal::string(string))
+        } else {
+            Literal::string(string)
         };
+        literal.set_span(span);
 
-        // #[doc = "..."]
-        //  ^^^^^^^^^^^^^
-        let doc_comment_square_brackets = match input.next() {
-            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
-            Some(tt) => {
-                compile_error(tt.span(), "expected `[...]`");
-                continue;
-            }
-            None => {
+        // concat!(file!(), ":", line!(), " ", "foo\nbar")
+        let mut literal = if self.modes.with_location.is_some() {
+            output::with_location(literal)
+        } else {
+            TokenTree::Literal(literal).into()
+        };
+
+        // concat!("foo", env!("BAR"), "baz")
+        //                ^^^^^^^^^^^^^^^^^^^ the tokens between the 2 regions of doc comments, then the 2nd string
+        if let (Some(middle), Some(suffix)) = (&self.middle, self.suffix_string()) {
+            let suffix_span = self
+                .doc_comments
+                .iter()
+                .find(|doc_comment| doc_comment.suffix)
+                .map_or(span, |doc_comment| doc_comment.span);
+            for mode in self.whole_string_modes() {
                 compile_error(
-                    doc_comment_start_span,
-                    "expected `#` to be followed by `[...]`",
+                    suffix_span,
+                    &format!(
+                        "`#[{mode}]` can't be used together with a 2nd region of doc comments"
+                    ),
                 );
-                continue;
             }
-        };
 
-        // Check if there is a doc comment after this one
-        //
-        // #[doc = "..."]            #[doc = "..."]
-        // ^^^^^^^^^^^^^^ current    ^ next?
-        match input.peek() {
-            Some(TokenTree::Punct(punct)) if *punct == '#' => {
-                // Yes, there is. Continue doc comment
+            // The `,` around the tokens are optional
+            let mut middle = middle.clone().into_iter().collect::<Vec<_>>();
+            if matches!(middle.first(), Some(TokenTree::Punct(comma)) if *comma == ',') {
+                middle.remove(0);
             }
-            _ => {
-                // The next token is not `#` so there are no more doc comments
-                doc_comment_progress = DocCommentProgress::Finished;
+            if matches!(middle.last(), Some(TokenTree::Punct(comma)) if *comma == ',') {
+                middle.pop();
             }
-        }
 
-        // #[doc = "..."]
-        //  ^^^^^^^^^^^^^
-        let mut doc_comment_attribute_inner = doc_comment_square_brackets.stream().into_iter();
+            let mut suffix = if self.modes.raw {
+                output::raw_string(&suffix).unwrap_or_else(|| Literal::string(&suffix))
+            } else {
+                Literal::string(&suffix)
+            };
+            suffix.set_span(span);
 
-        // #[doc = "..."]
-        //   ^^^
-        let kw_doc_span = match doc_comment_attribute_inner.next() {
-            Some(TokenTree::Ident(kw_doc)) if kw_doc.to_string() == "doc" => kw_doc.span(),
-            Some(tt) => {
-                compile_error(tt.span(), "expected `doc`");
-                continue;
+            literal.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+            if !middle.is_empty() {
+                literal.extend(middle);
+                literal.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
             }
-            None => {
+            literal.extend([TokenTree::Literal(suffix)]);
+        }
+
+        let Docstr {
+            modes,
+            macro_,
+            mut before,
+            mut after,
+            middle,
+            ..
+        } = self;
+
+        // docstr!(#[into = w] write! /// ...)
+        //                 ^ goes before everything else
+        if let Some(into) = modes.into {
+            if macro_.is_none() {
                 compile_error(
-                    doc_comment_square_brackets.span_open(),
-                    "expected `doc` after `[`",
+                    Span::call_site(),
+                    "`#[into]` requires a path to a macro like `write!`, which it is passed to",
                 );
-                continue;
+                return TokenStream::new();
             }
-        };
 
-        // #[doc = "..."]
-        //       ^
-        let punct_eq_span = match doc_comment_attribute_inner.next() {
-            Some(TokenTree::Punct(eq)) if eq == '=' => eq.span(),
-            Some(tt) => {
-                compile_error(tt.span(), "expected `=`");
-                continue;
+            let mut into_before = into;
+            into_before.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+            into_before.extend(before);
+            before = into_before;
+        }
+
+        let Some(macro_) = macro_ else {
+            if modes.at_placeholder {
+                compile_error(
+                    Span::call_site(),
+                    "`#[at_placeholder]` requires a path to a macro, with `@` in its arguments",
+                );
+                return TokenStream::new();
             }
-            None => {
-                compile_error(kw_doc_span, "expected `=` after `doc`");
-                continue;
+            if !before.is_empty() || !after.is_empty() || middle.is_some() {
+                compile_error(
+                    Span::call_site(),
+                    concat!(
+                        "expected macro input to only contain doc comments `///`, ",
+                        "because you haven't supplied a path to a macro as the 1st argument"
+                    ),
+                );
             }
+
+            // Just a plain string literal
+            return literal;
         };
 
-        // #[doc = "..."]
-        //         ^^^^^
-        let next = doc_comment_attribute_inner.next();
-        let Some(tt) = next else {
-            compile_error(punct_eq_span, "expected string literal after `=`");
-            continue;
+        let before_needs_comma = match before.clone().into_iter().last() {
+            Some(TokenTree::Punct(comma)) if comma == ',' => false,
+            Some(_) => true,
+            None => false,
         };
-        let span = tt.span();
 
-        // #[doc = "..."]
-        //          ^^^
-        let Ok(litrs::Literal::String(literal)) = litrs::Literal::try_from(tt) else {
-            compile_error(
-                span,
-                "only string \"...\" or r\"...\" literals are supported",
-            );
-            continue;
+        // docstr!(#[trim_trailing_comma] my_macro! /// ... a, b,)
+        //                                                      ^ removed
+        let after_needs_comma = if modes.trim_trailing_comma {
+            let mut tokens = after.into_iter().collect::<Vec<_>>();
+            if matches!(tokens.last(), Some(TokenTree::Punct(comma)) if *comma == ',') {
+                tokens.pop();
+            }
+            after = TokenStream::from_iter(tokens);
+            !after.is_empty()
+        } else {
+            true
         };
 
-        let literal = literal.value();
+        // docstr!(#[at_placeholder] my_macro! a, @, b /// ...)
+        //                                        ^ replaced with the string
+        if modes.at_placeholder {
+            let mut arguments = before.into_iter().collect::<Vec<_>>();
+            if before_needs_comma && !after.is_empty() {
+                arguments.push(TokenTree::Punct(Punct::new(',', Spacing::Alone)));
+            }
+            arguments.extend(after);
 
-        // Reached contents of the doc comment
-        //
-        // let's remove leading space
-        //
-        // /// foo bar
+            let mut placeholders = arguments
+                .iter()
+                .enumerate()
+                .filter(|(_, tt)| matches!(tt, TokenTree::Punct(punct) if *punct == '@'))
+                .map(|(index, tt)| (index, tt.span()));
+            let Some((index, _)) = placeholders.next() else {
+                compile_error(
+                    Span::call_site(),
+                    "`#[at_placeholder]` expects `@` in the arguments, where the string is passed",
+                );
+                return TokenStream::new();
+            };
+            for (_, span) in placeholders {
+                compile_error(span, "`@` can only be used once with `#[at_placeholder]`");
+            }
+            arguments.splice(index..=index, literal);
+
+            return macro_
+                .into_iter()
+                .chain([TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    TokenStream::from_iter(arguments),
+                ))])
+                .collect();
+        }
+
+        // The following:
         //
-        // this expands to:
+        // let a = docstr!(
+        //     format,
+        //     hello
+        //     /// foo
+        //     /// bar
+        //     a,
+        //     b
+        // );
         //
-        // #[doc = " foo bar"]
-        //          ^ remove this space from the actual output
+        // Expands into this:
         //
-        // We usually always have a space after the comment token,
-        // since it looks good. And e.g. Rustdoc ignores it as well.
-        let literal = literal.strip_prefix(' ').unwrap_or(literal);
+        // let a = format!(hello, "foo\nbar", a, b);
+        TokenStream::from_iter(
+            // format!(hello, "foo\nbar", a, b)
+            // ^^^^^^^
+            macro_.into_iter().chain([TokenTree::Group(Group::new(
+                // format!(hello, "foo\nbar", a, b)
+                //        ^                      ^
+                Delimiter::Parenthesis,
+                // format!(hello, "foo\nbar", a, b)
+                //         ^^^^^^^^^^^^^^^^^^^^^^^
+                TokenStream::from_iter(
+                    // format!(hello, "foo\nbar", a, b)
+                    //         ^^^^^^
+                    before
+                        .into_iter()
+                        // Comma before '#' is optional
+                        //
+                        // docstr!(writeln! w,
+                        //                   ^ this comma can be omitted
+                        //     #[doc = "..."]
+                        //     ^ next token
+                        // )
+                        .chain(
+                            before_needs_comma
+                                .then(|| TokenTree::Punct(Punct::new(',', Spacing::Joint))),
+                        )
+                        // format!(hello, "foo\nbar", a, b)
+                        //                ^^^^^^^^^^
+                        .chain(literal)
+                        // format!(hello, "foo\nbar", a, b)
+                        //                          ^
+                        .chain(
+                            after_needs_comma
+                                .then(|| TokenTree::Punct(Punct::new(',', Spacing::Joint))),
+                        )
+                        // format!(hello, "foo\nbar", a, b)
+                        //                            ^^^^
+                        .chain(after),
+                ),
+            ))]),
+        )
+    }
 
-        doc_comments.push(literal.to_string());
+    /// Whether any doc comment has a `#[cfg(...)]`, so the string is only known at runtime
+    fn has_cfg_lines(&self) -> bool {
+        self.doc_comments
+            .iter()
+            .any(|doc_comment| !doc_comment.cfg.is_empty())
     }
 
-    if doc_comments.is_empty() {
-        compile_error(
-            Span::call_site(),
-            "expected at least 1 documentation comment `/// ...`",
-        );
+    /// Report an error if any doc comment has a `#[cfg(...)]`, for macros which need the whole string
+    fn expect_no_cfg_lines(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        for doc_comment in &self.doc_comments {
+            if !doc_comment.cfg.is_empty() {
+                compile_error(
+                    doc_comment.span,
+                    &format!("`#[cfg(...)]` on doc comments is not supported by {what}, only by `docstr!`"),
+                );
+            }
+        }
     }
 
-    // The fully constructed string literal that we output
-    //
-    // docstr!(
-    //     /// foo
-    //     /// bar
-    // )
-    //
-    // becomes this:
-    //
-    // "foo\nbar"
-    let string = doc_comments
+    /// Join the lines at runtime, for doc comments with a `#[cfg(...)]` which the compiler evaluates
+    ///
+    /// ```ignore
+    /// <[&str]>::join(&[#[cfg(unix)] "foo", "bar"], "\n")
+    /// ```
+    fn cfg_lines(&self, compile_error: &mut dyn FnMut(Span, &str)) -> TokenStream {
+        let what = "`#[cfg(...)]` on doc comments";
+        self.expect_only_doc_comments(what, compile_error);
+        self.expect_no_output_mode(what, compile_error);
+
+        let modes = &self.modes;
+        let cfg_line = self
+            .doc_comments
+            .iter()
+            .find(|doc_comment| !doc_comment.cfg.is_empty())
+            .map_or_else(Span::call_site, |doc_comment| doc_comment.span);
+        let literal_modes = [
+            (modes.raw, "raw"),
+            (modes.with_location.is_some(), "with_location"),
+        ]
         .into_iter()
-        .reduce(|mut acc, s| {
-            acc.push('\n');
-            acc.push_str(&s);
-            acc
-        })
-        .unwrap_or_default();
-
-    let Some(macro_) = macro_ else {
-        if !before.is_empty() || !after.is_empty() {
+        .filter_map(|(used, mode)| used.then_some(mode));
+        for mode in self.whole_string_modes().chain(literal_modes) {
+            compile_error(
+                cfg_line,
+                &format!("`#[{mode}]` can't be used together with `#[cfg(...)]` on doc comments"),
+            );
+        }
+
+        let mut lines = TokenStream::new();
+        for doc_comment in &self.doc_comments {
+            let mut text = match modes.case {
+                Some(Case::Upper) => doc_comment.text.to_uppercase(),
+                Some(Case::Lower) => doc_comment.text.to_lowercase(),
+                None => doc_comment.text.clone(),
+            };
+            if let Some(normalization) = modes.normalization {
+                text = transform::normalize(&text, normalization);
+            }
+            lines.extend(doc_comment.cfg.clone());
+            lines.extend([
+                TokenTree::Literal(Literal::string(&text)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+
+        // The type is written out, because the array is empty if no lines are included
+        let mut join = "<[&str]>::join"
+            .parse::<TokenStream>()
+            .expect("path is valid");
+        join.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([
+                TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+                TokenTree::Group(Group::new(Delimiter::Bracket, lines)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                TokenTree::Literal(Literal::string(modes.join.as_deref().unwrap_or("\n"))),
+            ]),
+        ))]);
+        join
+    }
+
+    /// Report an error if `#[unescape]` is used, which only makes sense for bytes
+    fn expect_no_unescape(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        if let Some(span) = self.modes.unescape {
+            compile_error(
+                span,
+                &format!("`#[unescape]` is not supported by {what}, only by `docstr_bytes!`"),
+            );
+        }
+    }
+
+    /// Report an error if `#[delimiter]` is used, which only makes sense for key-value pairs
+    fn expect_no_delimiter(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        if let Some((span, _)) = &self.modes.delimiter {
+            compile_error(
+                *span,
+                &format!("`#[delimiter]` is not supported by {what}, only by `docstr_map!` and `#[emit_static_str_map_sorted]`"),
+            );
+        }
+    }
+
+    /// Report an error if `#[with_location]` is used, for macros which need the whole string at compile-time
+    fn expect_no_with_location(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        if let Some(span) = self.modes.with_location {
+            compile_error(
+                span,
+                &format!("`#[with_location]` is not supported by {what}"),
+            );
+        }
+    }
+
+    /// Report an error if a mode from [`Output`] was passed,
+    /// for macros which always generate a string
+    fn expect_no_output_mode(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        if let Some((name, _)) = &self.modes.output {
+            compile_error(
+                name.span(),
+                &format!("`#[{name}]` is not supported by {what}"),
+            );
+        }
+    }
+
+    /// Report an error if anything other than doc comments was passed,
+    /// for outputs which can't be passed to a macro
+    fn expect_only_doc_comments(&self, what: &str, compile_error: &mut dyn FnMut(Span, &str)) {
+        if self.macro_.is_some()
+            || !self.before.is_empty()
+            || !self.after.is_empty()
+            || self.middle.is_some()
+        {
             compile_error(
                 Span::call_site(),
-                concat!(
-                    "expected macro input to only contain doc comments `///`, ",
-                    "because you haven't supplied a path to a macro as the 1st argument"
-                ),
+                &format!("{what} only accepts doc comments `///`, it can't be passed to a macro"),
             );
         }
+    }
+}
+
+/// Call `path`, which is relative to `alloc` like `boxed::Box::<str>::from`, with `args`
+///
+/// Without the `alloc` feature, this is `::std::$path($args)`. With it, the path goes through
+/// `alloc` which is available without `std`, and which needs an `extern crate` even with `std`:
+///
+/// ```ignore
+/// {
+///     extern crate alloc as docstr_alloc;
+///     docstr_alloc::$path($args)
+/// }
+/// ```
+fn alloc_call(path: &str, args: TokenStream) -> TokenStream {
+    let call = |krate: &str| {
+        let mut call = format!("{krate}::{path}")
+            .parse::<TokenStream>()
+            .expect("path is valid");
+        call.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+        call
+    };
+
+    if cfg!(feature = "alloc") {
+        let mut block = "extern crate alloc as docstr_alloc;"
+            .parse::<TokenStream>()
+            .expect("item is valid");
+        block.extend(call("docstr_alloc"));
+        TokenTree::Group(Group::new(Delimiter::Brace, block)).into()
+    } else {
+        call("::std")
+    }
+}
+
+/// Path to a macro as it would be written, `std::format` instead of `std :: format`
+fn path_to_string(path: &TokenStream) -> String {
+    path.clone().into_iter().map(|tt| tt.to_string()).collect()
+}
+
+/// Whether the `[...]` after a `#` is `[doc ...]`, which may be invalid but is meant to be a doc comment
+fn is_doc_attribute(brackets: &Group) -> bool {
+    brackets.delimiter() == Delimiter::Bracket
+        && matches!(brackets.stream().into_iter().next(), Some(TokenTree::Ident(doc)) if doc.to_string() == "doc")
+}
+
+/// Whether the `[...]` after a `#` is `[cfg(...)]`
+fn is_cfg_attribute(brackets: &Group) -> bool {
+    let mut tokens = brackets.stream().into_iter();
+    matches!(tokens.next(), Some(TokenTree::Ident(cfg)) if cfg.to_string() == "cfg")
+        && matches!(tokens.next(), Some(TokenTree::Group(predicate)) if predicate.delimiter() == Delimiter::Parenthesis)
+        && tokens.next().is_none()
+}
+
+/// Contents of a doc comment attribute, `#[doc = "..."]` becomes `...`
+///
+/// `brackets` is the `[...]` after the `#`. Returns `None` after reporting an error with `compile_error`
+fn parse_doc_attribute(
+    brackets: &Group,
+    compile_error: &mut impl FnMut(Span, &str),
+) -> Option<String> {
+    // #[doc = "..."]
+    //  ^^^^^^^^^^^^^
+    let mut doc_comment_attribute_inner = brackets.stream().into_iter().peekable();
+
+    // #[doc = "..."]
+    //   ^^^
+    let kw_doc_span = match doc_comment_attribute_inner.next() {
+        Some(TokenTree::Ident(kw_doc)) if kw_doc.to_string() == "doc" => kw_doc.span(),
+        // The whole `[...]` is underlined, which is easier to find in long generated attributes
+        Some(_) => {
+            compile_error(brackets.span(), "expected `doc`");
+            return None;
+        }
+        None => {
+            compile_error(brackets.span_open(), "expected `doc` after `[`");
+            return None;
+        }
+    };
 
-        if !compile_errors.is_empty() {
-            return compile_errors;
+    // #[doc = "..."]
+    //       ^
+    match doc_comment_attribute_inner.next() {
+        Some(TokenTree::Punct(eq)) if eq == '=' => (),
+        // #[doc(hidden)], #[doc(alias = "...")]...
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            compile_error(
+                group.span(),
+                "`#[doc(...)]` attributes are not supported, only doc comments like `/// ...` or `#[doc = \"...\"]`",
+            );
+            return None;
+        }
+        Some(_) => {
+            compile_error(brackets.span(), "expected `=`");
+            return None;
+        }
+        None => {
+            compile_error(kw_doc_span, "expected `=` after `doc`");
+            return None;
         }
+    }
 
-        // Just a plain string literal
-        return TokenTree::Literal(Literal::string(&string)).into();
+    // #[doc = "..."]
+    //         ^^^^^
+    let next = doc_comment_attribute_inner.next();
+    let Some(tt) = next.map(unwrap_invisible_group) else {
+        compile_error(brackets.span(), "expected string literal after `=`");
+        return None;
     };
+    let span = tt.span();
 
-    if !compile_errors.is_empty() {
-        return compile_errors;
+    // #[doc = include_str!("...")]
+    //         ^^^^^^^^^^^ macros in attributes are expanded after `docstr!` runs
+    if let TokenTree::Ident(name) = &tt {
+        if matches!(doc_comment_attribute_inner.peek(), Some(TokenTree::Punct(p)) if *p == '!') {
+            let help = if name.to_string() == "include_str" {
+                "use `include_str!(\"...\")` on its own, or combine it with the doc comments \
+                 using `concat!(docstr!(...), include_str!(\"...\"))`"
+            } else {
+                "use a string literal `#[doc = \"...\"]`"
+            };
+            compile_error(
+                span,
+                &format!("`{name}!` can't be used in `#[doc = ...]`, because the macro is only expanded after `docstr!` reads the doc comments\n\nhelp: {help}"),
+            );
+            return None;
+        }
     }
 
-    // The following:
-    //
-    // let a = docstr!(
-    //     format,
-    //     hello
-    //     /// foo
-    //     /// bar
-    //     a,
-    //     b
-    // );
-    //
-    // Expands into this:
+    // #[doc = "..."]
+    //          ^^^
+    let Ok(litrs::Literal::String(literal)) = litrs::Literal::try_from(tt) else {
+        compile_error(
+            brackets.span(),
+            "only string \"...\" or r\"...\" literals are supported",
+        );
+        return None;
+    };
+
+    let mut literal = literal.into_value();
+
+    // Tools generating doc attributes may split the content
+    // across adjacent literals, which we concatenate
     //
-    // let a = format!(hello, "foo\nbar", a, b);
-    TokenStream::from_iter(
-        // format!(hello, "foo\nbar", a, b)
-        // ^^^^^^^
-        macro_.into_iter().chain([TokenTree::Group(Group::new(
-            // format!(hello, "foo\nbar", a, b)
-            //        ^                      ^
-            Delimiter::Parenthesis,
-            // format!(hello, "foo\nbar", a, b)
-            //         ^^^^^^^^^^^^^^^^^^^^^^^
-            TokenStream::from_iter(
-                // format!(hello, "foo\nbar", a, b)
-                //         ^^^^^^
-                before
-                    .into_iter()
-                    .chain([
-                        // format!(hello, "foo\nbar", a, b)
-                        //                ^^^^^^^^^^
-                        TokenTree::Literal(Literal::string(&string)),
-                        // format!(hello, "foo\nbar", a, b)
-                        //                          ^
-                        TokenTree::Punct(Punct::new(',', Spacing::Joint)),
-                    ])
-                    // format!(hello, "foo\nbar", a, b)
-                    //                            ^^^^
-                    .chain(after),
-            ),
-        ))]),
-    )
+    // #[doc = "..." "..."]
+    //               ^^^^^
+    for tt in doc_comment_attribute_inner {
+        let span = tt.span();
+        match litrs::Literal::try_from(unwrap_invisible_group(tt)) {
+            Ok(litrs::Literal::String(next)) => literal.push_str(next.value()),
+            _ => {
+                compile_error(span, "expected string literal or `]`");
+                return None;
+            }
+        }
+    }
+
+    Some(literal)
+}
+
+/// `macro_rules!` wraps fragments like `$line:literal` in an invisible group
+/// when passing them to a proc macro, so `#[doc = $line]` has a group instead of a literal
+fn unwrap_invisible_group(tt: TokenTree) -> TokenTree {
+    match tt {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+            let mut tokens = group.stream().into_iter();
+            match (tokens.next(), tokens.next()) {
+                (Some(tt), None) => unwrap_invisible_group(tt),
+                _ => TokenTree::Group(group),
+            }
+        }
+        tt => tt,
+    }
+}
+
+/// A single doc comment `/// ...`
+struct DocComment {
+    /// Contents of the doc comment, without the leading space
+    text: String,
+    /// Span of the whole doc comment
+    ///
+    /// ```ignore
+    /// /// foo bar
+    /// ^^^^^^^^^^^
+    /// ```
+    span: Span,
+    /// `#[cfg(...)]` attributes before the doc comment, empty if it is always included
+    cfg: TokenStream,
+    /// Whether the doc comment is in the 2nd region of doc comments, after the tokens between them
+    suffix: bool,
 }
 
 /// `.into_iter()` generates `compile_error!($message)` at `$span`
 struct CompileError {
     /// Where the compile error is generates
     pub span: Span,
+    /// Where the compile error ends, the same as `span` unless it covers several tokens
+    pub end: Span,
     /// Message of the compile error
     pub message: String,
 }
@@ -521,11 +4022,39 @@ struct CompileError {
 impl CompileError {
     /// Create a new compile error
     pub fn new(span: Span, message: impl AsRef<str>) -> Self {
+        Self::spanning(span, span, message)
+    }
+
+    /// Create a compile error which underlines everything from `start` to `end`
+    pub fn spanning(start: Span, end: Span, message: impl AsRef<str>) -> Self {
         Self {
-            span,
+            span: start,
+            end,
             message: message.as_ref().to_string(),
         }
     }
+
+    /// Generate all of the compile errors, but each error with the same message and spans only once
+    pub fn deduplicated(compile_errors: Vec<Self>) -> TokenStream {
+        // `Span` can't be compared, but its `Debug` contains the location in the source code
+        let key = |error: &Self| {
+            (
+                format!("{:?} {:?}", error.span, error.end),
+                error.message.clone(),
+            )
+        };
+
+        let mut seen = Vec::new();
+        let mut tokens = TokenStream::new();
+        for error in compile_errors {
+            let key = key(&error);
+            if !seen.contains(&key) {
+                seen.push(key);
+                tokens.extend(error);
+            }
+        }
+        tokens
+    }
 }
 
 impl IntoIterator for CompileError {
@@ -548,7 +4077,9 @@ impl IntoIterator for CompileError {
                         string
                     })])
                 });
-                group.set_span(self.span);
+                // rustc underlines from the start of `compile_error!` to the end of `{...}`,
+                // so this is how a single error can cover several tokens on stable
+                group.set_span(self.end);
                 group
             }),
         ]
@@ -565,8 +4096,12 @@ impl IntoIterator for CompileError {
 ///     // DocComments::Inside
 ///     /// foo
 ///     /// bar
-///     // DocComments::Finished
+///     // DocComments::Between
 ///     and here too
+///     // DocComments::InsideSuffix
+///     /// 2nd region of doc comments, which is optional
+///     // DocComments::Finished
+///     and here
 /// )
 /// ```
 #[derive(Eq, PartialEq, PartialOrd, Ord)]
@@ -575,6 +4110,10 @@ enum DocCommentProgress {
     NotReached,
     /// currently we are INSIDE the doc comments
     Inside,
+    /// After the 1st region of doc comments, a 2nd one can still start
+    Between,
+    /// currently we are INSIDE the 2nd region of doc comments
+    InsideSuffix,
     /// We have parsed all the doc comments
     Finished,
 }
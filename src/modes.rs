@@ -0,0 +1,1418 @@
+//! Modes change how `docstr!` processes its input.
+//!
+//! They are written as attributes at the very start of the macro input,
+//! before the macro path:
+//!
+//! ```ignore
+//! docstr!(#[deflate = TEMPLATE]
+//!     /// foo
+//!     /// bar
+//! );
+//! ```
+//!
+//! An attribute is a mode when its first token is an identifier other than `doc` or `cfg`.
+//! `#[doc = "..."]` is what `/// ...` expands to, so that one is a doc comment,
+//! and `#[cfg(...)]` only includes the doc comment after it if the cfg holds.
+//!
+//! Before the attributes, the most common flags can be combined into a group of short flags,
+//! see [`SHORT_FLAGS`]:
+//!
+//! ```ignore
+//! docstr!([d, t]
+//!     /// foo
+//! );
+//! ```
+
+use proc_macro::{
+    token_stream, Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree,
+};
+use std::collections::VecDeque;
+use std::iter::Peekable;
+
+/// Short flags in `[d, t]`, and the mode which each of them stands for
+const SHORT_FLAGS: &[(&str, &str)] = &[
+    ("d", "dedent"),
+    ("t", "trim_blank_lines"),
+    ("r", "raw"),
+    ("n", "number"),
+];
+
+/// All modes that have been passed to `docstr!`
+#[derive(Default)]
+pub struct Modes {
+    /// What to generate instead of a string, together with the name of the mode
+    pub output: Option<(Ident, Output)>,
+    /// `#[normalize_newlines]` or `#[normalize_newlines = all]`
+    ///
+    /// Remove `\r` at the end of each line, or every `\r` in the content
+    pub normalize_newlines: Option<NormalizeNewlines>,
+    /// `#[into = w]`
+    ///
+    /// Pass `w` as the 1st argument to the macro, before everything else
+    pub into: Option<TokenStream>,
+    /// `#[raw]`
+    ///
+    /// Generate a raw string literal `r#"..."#` instead of escaping the string
+    pub raw: bool,
+    /// `#[unescape]`
+    ///
+    /// Interpret escapes like `\xFF` as the bytes they stand for, only with `docstr_bytes!`.
+    /// Span of the `unescape`, for reporting that it is not supported
+    pub unescape: Option<Span>,
+    /// `#[with_location]`
+    ///
+    /// Prefix the string with `file:line ` of the invocation, using `file!()` and `line!()`.
+    /// Span of the `with_location`, for reporting that it is not supported
+    pub with_location: Option<Span>,
+    /// `#[strip_ansi]`
+    ///
+    /// Remove ANSI escape sequences like `\x1b[31m` from each line
+    pub strip_ansi: bool,
+    /// `#[strip_comments = "#"]`
+    ///
+    /// Remove the lines which start with this comment marker, after any indentation
+    pub strip_comments: Option<String>,
+    /// `#[trim_blank_lines]`
+    ///
+    /// Remove blank lines at the start and at the end
+    pub trim_blank_lines: bool,
+    /// `#[dedent]`
+    ///
+    /// Remove the indentation which is common to all lines
+    pub dedent: bool,
+    /// `#[dedent_first]`
+    ///
+    /// Remove the indentation of the first line which isn't blank from all lines
+    pub dedent_first: bool,
+    /// `#[lines = A..B]`
+    ///
+    /// Only keep the lines in this range
+    pub lines: Option<LineRange>,
+    /// `#[strip = N]` or `#[space_strip = all|one|none]`
+    ///
+    /// Remove up to `N` leading spaces from each line, instead of just 1
+    pub strip: Option<usize>,
+    /// `#[trim_trailing_comma]`
+    ///
+    /// Don't pass a trailing comma to the macro
+    pub trim_trailing_comma: bool,
+    /// `#[at_placeholder]`
+    ///
+    /// Pass the string where `@` is in the arguments, instead of between the tokens before and after the doc comments
+    pub at_placeholder: bool,
+    /// `#[explicit_trailing]`
+    ///
+    /// Remove a `\` at the end of each line, so whitespace before it is kept
+    pub explicit_trailing: bool,
+    /// `#[tabs = N]`
+    ///
+    /// Expand tabs to spaces, with a tab stop every `N` columns
+    pub tabs: Option<usize>,
+    /// `#[subst(NAME = "value")]`
+    ///
+    /// Replace each `{NAME}` with `value`
+    pub subst: Option<Vec<(String, String)>>,
+    /// `#[strip_doc_links]`
+    ///
+    /// Replace Markdown links like `[text](url)` and `` [`Type`] `` with just their text
+    pub strip_doc_links: bool,
+    /// `#[placeholder_style = printf]`
+    ///
+    /// Convert printf-style placeholders like `%s` to Rust's `{}`
+    pub printf_placeholders: bool,
+    /// `#[brace_unescape]`
+    ///
+    /// Without a macro, turn `{{` into `{` and `}}` into `}` like `format!` does
+    pub brace_unescape: bool,
+    /// `#[reverse_lines]`
+    ///
+    /// Reverse the order of the lines, blank lines at the end stay there
+    pub reverse_lines: bool,
+    /// `#[wrap = N]`
+    ///
+    /// Wrap each line at word boundaries, so it is at most `N` characters wide
+    pub wrap: Option<usize>,
+    /// `#[hang = M]`
+    ///
+    /// Indent lines that were wrapped by `#[wrap = N]` with `M` spaces
+    pub hang: Option<usize>,
+    /// `#[wrap_preserve_indent]`
+    ///
+    /// Indent lines that were wrapped by `#[wrap = N]` like the line they were wrapped from
+    pub wrap_preserve_indent: bool,
+    /// `#[emit_toc]`
+    ///
+    /// Prepend a table of contents, linking to each Markdown heading
+    pub toc: bool,
+    /// `#[escape_for_makefile]`
+    ///
+    /// Double every `$`, so Make doesn't expand it as a variable
+    pub escape_for_makefile: bool,
+    /// `#[line_hash_comments]`
+    ///
+    /// Append `# crc:xxxxxxxx` to each line, the CRC-32 of that line
+    pub line_hash_comments: bool,
+    /// `#[number]`
+    ///
+    /// Prefix each line with its number
+    pub number: bool,
+    /// `#[number_from = N]`
+    ///
+    /// Number of the 1st line with `#[number]`, instead of 1
+    pub number_from: Option<usize>,
+    /// `#[number_format = "{n}| "]`
+    ///
+    /// Prefix of each line with `#[number]`, where `{n}` is the number of the line
+    pub number_format: Option<String>,
+    /// `#[number_skip_blank]`
+    ///
+    /// With `#[number]`, don't number blank lines
+    pub number_skip_blank: bool,
+    /// `#[diff_markers]` or `#[as_diff = added]`
+    ///
+    /// Prefix lines with the markers of a unified diff
+    pub diff: Option<Diff>,
+    /// `#[delimiter = ":"]`
+    ///
+    /// Separates the key from the value in `docstr_map!` and `#[emit_static_str_map_sorted]`.
+    /// Span of the `delimiter`, for reporting that it is not supported
+    pub delimiter: Option<(Span, String)>,
+    /// `#[strict_keys]`
+    ///
+    /// Duplicate keys in `#[emit_static_str_map_sorted]` are an error
+    pub strict_keys: bool,
+    /// `#[validate_no_trailing_whitespace]`
+    ///
+    /// Report an error for each line that ends with whitespace
+    pub validate_no_trailing_whitespace: bool,
+    /// `#[validate = json]`
+    ///
+    /// Report an error if the lines are not valid JSON
+    pub validate_json: bool,
+    /// `#[check_braces]`
+    ///
+    /// Report an error for each `{` which is never closed, and each `}` which is never opened
+    pub check_braces: bool,
+    /// `#[allow_empty]`
+    ///
+    /// Generate an empty string when there are no doc comments, instead of reporting an error
+    pub allow_empty: bool,
+    /// `#[join = ", "]`
+    ///
+    /// Join lines with this string instead of `\n`
+    pub join: Option<String>,
+    /// `#[repeat = N]`
+    ///
+    /// Repeat the lines `N` times
+    pub repeat: Option<usize>,
+    /// `#[upper]` or `#[lower]`
+    ///
+    /// Convert the string to uppercase or lowercase
+    pub case: Option<Case>,
+    /// `#[nfc]` or `#[nfd]`
+    ///
+    /// Normalize the string to this Unicode normalization form
+    pub normalization: Option<Normalization>,
+    /// `#[leading_newline]`
+    ///
+    /// Start the content with `\n`, unless it already starts with one
+    pub leading_newline: bool,
+    /// `#[strip_final_newline]`
+    ///
+    /// Remove the `\n` at the end of the content, if there is one
+    pub strip_final_newline: bool,
+    /// `#[ensure_shebang = "/bin/sh"]`
+    ///
+    /// Add `#!/bin/sh` as the first line, unless the content already starts with `#!`
+    pub ensure_shebang: Option<String>,
+    /// `#[emit_raw_bytes_hex_dump]`
+    ///
+    /// Replace the content with a canonical hex dump of its bytes, like `hexdump -C`
+    pub hex_dump: bool,
+    /// `#[wrap_in("<!-- ", " -->")]`
+    ///
+    /// Add the 1st string before the whole content, and the 2nd string after it
+    pub wrap_in: Option<(String, String)>,
+}
+
+/// Modes which generate something other than a string.
+///
+/// Only 1 of these can be used at a time, and they can't be passed to a macro
+pub enum Output {
+    /// `#[deflate = NAME]`
+    ///
+    /// Compress the string and emit it as `const NAME: &[u8]`,
+    /// together with `const NAME_LEN: usize` which is the decompressed length
+    Deflate(ItemName),
+    /// `#[emit_match_arms]`
+    ///
+    /// Each line is a match arm, and tokens before the doc comments are the scrutinee
+    MatchArms,
+    /// `#[emit_enum_variants]`
+    ///
+    /// Each line is a variant of an `enum`, and tokens before the doc comments are its header.
+    /// Contains the attributes of the `enum`, like `derive(Debug), repr(u8)`
+    EnumVariants(TokenStream),
+    /// `#[emit_byte_match]`
+    ///
+    /// Like `#[emit_match_arms]`, but each pattern must be a `u8` like `0x01` or `b'a'`
+    ByteMatch,
+    /// `#[emit_const_str_slice_of_bytes = NAME]`
+    ///
+    /// Emit the string as `const NAME: &[u8]`, where each byte is an integer literal
+    ByteSlice(ItemName),
+    /// `#[to_const_option = NAME]`
+    ///
+    /// Emit `const NAME: Option<&str>`, which is `None` if the string is empty
+    ConstOption(ItemName),
+    /// `#[emit_const_array_2d = NAME]`
+    ///
+    /// Emit `const NAME: &[&[&str]]`, where each line is a row of cells separated by `|`
+    Array2d(ItemName),
+    /// `#[measure_const = NAME]`
+    ///
+    /// Emit `const NAME: (usize, usize)` with the width of the widest line, and the number of lines
+    Measure(ItemName),
+    /// `#[emit_static_str_map_sorted = NAME]`
+    ///
+    /// Emit `const NAME: &[(&str, &str)]` from `key: value` lines, sorted by the key
+    SortedMap(ItemName),
+    /// `#[emit_const_u8_slices_per_line = NAME]`
+    ///
+    /// Emit `const NAME: &[&[u8]]`, where each element is the bytes of a line
+    ByteLines(ItemName),
+    /// `#[static_ref = NAME]`
+    ///
+    /// Emit the string as `static NAME: &str`, so every use of it shares the same storage
+    Static(ItemName),
+    /// `#[named = NAME]`
+    ///
+    /// Emit the string as `const NAME: &str`, together with a hidden item named after a hash of the
+    /// string, so the same string can only be defined once per module
+    Named(ItemName),
+    /// `#[chunk_lines(N)]`
+    ///
+    /// Emit `&[&str]` where each element is up to `N` lines
+    ChunkLines(usize),
+    /// `#[call = builder.text]`
+    ///
+    /// Call the function or method with the string as its only argument
+    Call(TokenStream),
+    /// `#[join_expr = sep]`
+    ///
+    /// Join the lines at runtime with `.join(sep)`, producing a `String`
+    JoinExpr(TokenStream),
+    /// `#[extend = buf]`
+    ///
+    /// Append the string to `buf`, which is a `String` for `docstr!` and a `Vec<u8>` for `docstr_bytes!`
+    Extend(TokenStream),
+    /// `#[write_all = io]`
+    ///
+    /// Write the bytes of the string to `io` with `std::io::Write::write_all`
+    WriteAll(TokenStream),
+    /// `#[emit_writeln_sequence]`
+    ///
+    /// Call `writeln!` once for each line, the writer is before the doc comments
+    WritelnSequence,
+}
+
+/// Which `\r` characters `#[normalize_newlines]` removes
+#[derive(Clone, Copy)]
+pub enum NormalizeNewlines {
+    /// `#[normalize_newlines]`: only `\r` at the end of every line
+    Trailing,
+    /// `#[normalize_newlines = all]`: every `\r`, including inside lines
+    All,
+}
+
+/// How `#[diff_markers]` and `#[as_diff]` prefix the lines
+pub enum Diff {
+    /// `#[diff_markers]`: lines which don't start with `+`, `-` or ` ` get this prefix
+    Markers(String),
+    /// `#[as_diff = added]` or `#[as_diff = removed]`: every line gets this prefix
+    All(char),
+}
+
+/// Which case `#[upper]` and `#[lower]` convert the string to
+#[derive(Clone, Copy)]
+pub enum Case {
+    /// `#[upper]`
+    Upper,
+    /// `#[lower]`
+    Lower,
+}
+
+/// Unicode normalization form of `#[nfc]` and `#[nfd]`
+#[derive(Clone, Copy)]
+pub enum Normalization {
+    /// `#[nfc]`, composed: `e` followed by a combining acute accent becomes `é`
+    Nfc,
+    /// `#[nfd]`, decomposed: `é` becomes `e` followed by a combining acute accent
+    Nfd,
+}
+
+/// Range of lines kept by `#[lines = A..B]`, indices start at 0
+pub struct LineRange {
+    /// Index of the first line that is kept
+    pub start: usize,
+    /// Index after the last line that is kept, `None` for all lines until the end
+    pub end: Option<usize>,
+    /// Span of the range, for reporting that it is out of bounds
+    pub span: Span,
+}
+
+/// Name of an item that `docstr!` generates, with an optional visibility
+///
+/// ```ignore
+/// #[deflate = pub(crate) NAME]
+/// //          ^^^^^^^^^^ vis
+/// //                     ^^^^ ident
+/// ```
+pub struct ItemName {
+    /// Visibility of the item, empty for private items
+    pub vis: TokenStream,
+    /// Name of the item
+    pub ident: Ident,
+}
+
+impl Modes {
+    /// Parse all the modes at the start of the input.
+    ///
+    /// Stops at the first token which is not the start of a mode attribute
+    pub fn parse(
+        input: &mut Peekable<token_stream::IntoIter>,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Self {
+        let mut modes = Self::default();
+        // Names of all the modes we have seen so far
+        let mut seen = Vec::new();
+        // `#[hang]` only makes sense together with `#[wrap]`, which may come after it
+        let mut hang_span = None;
+        // Same for `#[wrap_preserve_indent]`
+        let mut wrap_preserve_indent_span = None;
+        // `#[number_from]`, `#[number_format]` and `#[number_skip_blank]` need `#[number]`
+        let mut number_options = Vec::new();
+        // `#[dedent_first]` and `#[dedent]` are 2 different ways to remove indentation
+        let mut dedent_first_span = None;
+        // `#[strict_keys]` only makes sense together with `#[emit_static_str_map_sorted]`
+        let mut strict_keys_span = None;
+
+        // Modes which have been parsed, but not applied yet
+        let mut queued = VecDeque::new();
+
+        // docstr!([d, t] ...)
+        //         ^^^^^^ short flags come before the attributes
+        if let Some(TokenTree::Group(group)) = input.peek() {
+            if group.delimiter() == Delimiter::Bracket {
+                queued.extend(parse_short_flags(group, compile_error));
+                input.next();
+                // docstr!([d, t], ...)
+                //               ^ optional
+                if matches!(input.peek(), Some(TokenTree::Punct(comma)) if *comma == ',') {
+                    input.next();
+                }
+            }
+        }
+
+        loop {
+            let (name, value) = match queued.pop_front() {
+                Some(mode) => mode,
+                None => match parse_attribute(input, compile_error) {
+                    Some(modes) => {
+                        queued.extend(modes);
+                        continue;
+                    }
+                    None => break,
+                },
+            };
+
+            let name_str = name.to_string();
+            if seen.contains(&name_str) {
+                compile_error(name.span(), &format!("`#[{name}]` is used more than once"));
+                continue;
+            }
+            seen.push(name_str);
+
+            let output = match name.to_string().as_str() {
+                "deflate" => {
+                    if !cfg!(feature = "compress") {
+                        compile_error(
+                            name.span(),
+                            "`#[deflate]` requires the `compress` feature of `docstr`",
+                        );
+                    }
+                    value.item_name(&name, compile_error).map(Output::Deflate)
+                }
+                "emit_const_str_slice_of_bytes" => {
+                    value.item_name(&name, compile_error).map(Output::ByteSlice)
+                }
+                "to_const_option" => value
+                    .item_name(&name, compile_error)
+                    .map(Output::ConstOption),
+                "emit_const_array_2d" => value.item_name(&name, compile_error).map(Output::Array2d),
+                "measure_const" => value.item_name(&name, compile_error).map(Output::Measure),
+                "emit_static_str_map_sorted" => {
+                    value.item_name(&name, compile_error).map(Output::SortedMap)
+                }
+                "emit_const_u8_slices_per_line" => {
+                    value.item_name(&name, compile_error).map(Output::ByteLines)
+                }
+                "static_ref" => value.item_name(&name, compile_error).map(Output::Static),
+                "named" => value.item_name(&name, compile_error).map(Output::Named),
+                "chunk_lines" => value
+                    .positive_integer(&name, compile_error)
+                    .map(Output::ChunkLines),
+                "emit_match_arms" => value.flag(&name, compile_error).map(|()| Output::MatchArms),
+                "emit_enum_variants" => match value {
+                    AttrValue::None => Some(Output::EnumVariants(TokenStream::new())),
+                    AttrValue::Args(attributes) => Some(Output::EnumVariants(attributes.stream())),
+                    value => {
+                        compile_error(
+                            value.span(&name),
+                            "expected `#[emit_enum_variants]` or `#[emit_enum_variants(derive(Debug))]`",
+                        );
+                        None
+                    }
+                },
+                "emit_byte_match" => value.flag(&name, compile_error).map(|()| Output::ByteMatch),
+                "call" => value
+                    .tokens(&name, "builder.text", compile_error)
+                    .map(Output::Call),
+                "join_expr" => value
+                    .tokens(&name, "sep", compile_error)
+                    .map(Output::JoinExpr),
+                "extend" => value
+                    .tokens(&name, "buf", compile_error)
+                    .map(Output::Extend),
+                "write_all" => value
+                    .tokens(&name, "io", compile_error)
+                    .map(Output::WriteAll),
+                "emit_writeln_sequence" => value
+                    .flag(&name, compile_error)
+                    .map(|()| Output::WritelnSequence),
+                "normalize_newlines" => {
+                    modes.normalize_newlines = match value {
+                        AttrValue::None => Some(NormalizeNewlines::Trailing),
+                        value => value
+                            .keyword(&name, &["all"], compile_error)
+                            .map(|_| NormalizeNewlines::All),
+                    };
+                    None
+                }
+                "into" => {
+                    modes.into = value.tokens(&name, "w", compile_error);
+                    None
+                }
+                "strip_comments" => {
+                    let span = value.span(&name);
+                    modes.strip_comments = value.string(&name, "#", compile_error);
+                    if modes.strip_comments.as_deref() == Some("") {
+                        compile_error(span, "the comment marker can't be empty");
+                    }
+                    None
+                }
+                "trim_blank_lines" => {
+                    modes.trim_blank_lines = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "dedent" => {
+                    modes.dedent = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "dedent_first" => {
+                    dedent_first_span = Some(name.span());
+                    modes.dedent_first = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "lines" => {
+                    modes.lines = value.range(&name, compile_error);
+                    None
+                }
+                "strip_ansi" => {
+                    modes.strip_ansi = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "raw" => {
+                    modes.raw = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "with_location" => {
+                    modes.with_location = value.flag(&name, compile_error).map(|()| name.span());
+                    None
+                }
+                "unescape" => {
+                    modes.unescape = value.flag(&name, compile_error).map(|()| name.span());
+                    None
+                }
+                "strip" | "space_strip" => {
+                    let other = if name.to_string() == "strip" {
+                        "space_strip"
+                    } else {
+                        "strip"
+                    };
+                    if seen.iter().any(|seen| seen == other) {
+                        compile_error(
+                            name.span(),
+                            &format!("`#[{name}]` can't be used together with `#[{other}]`"),
+                        );
+                    } else if other == "space_strip" {
+                        modes.strip = value.integer(&name, 0, compile_error);
+                    } else {
+                        // #[space_strip = all] is the same as #[strip = N] with an unlimited N
+                        modes.strip = value
+                            .keyword(&name, &["all", "one", "none"], compile_error)
+                            .map(|index| [usize::MAX, 1, 0][index]);
+                    }
+                    None
+                }
+                "at_placeholder" => {
+                    modes.at_placeholder = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "explicit_trailing" => {
+                    modes.explicit_trailing = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "trim_trailing_comma" => {
+                    modes.trim_trailing_comma = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "tabs" => {
+                    modes.tabs = value.positive_integer(&name, compile_error);
+                    None
+                }
+                "subst" => {
+                    modes.subst = value.substitutions(&name, compile_error);
+                    None
+                }
+                "strip_doc_links" => {
+                    modes.strip_doc_links = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "placeholder_style" => {
+                    modes.printf_placeholders =
+                        value.keyword(&name, &["printf"], compile_error).is_some();
+                    None
+                }
+                "reverse_lines" => {
+                    modes.reverse_lines = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "brace_unescape" => {
+                    modes.brace_unescape = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "wrap" => {
+                    modes.wrap = value.positive_integer(&name, compile_error);
+                    None
+                }
+                "hang" => {
+                    hang_span = Some(name.span());
+                    modes.hang = value.positive_integer(&name, compile_error);
+                    None
+                }
+                "wrap_preserve_indent" => {
+                    wrap_preserve_indent_span = Some(name.span());
+                    modes.wrap_preserve_indent = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "emit_toc" => {
+                    modes.toc = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "escape_for_makefile" => {
+                    modes.escape_for_makefile = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "number" => {
+                    modes.number = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "number_from" => {
+                    number_options.push(name.clone());
+                    modes.number_from = value.integer(&name, 0, compile_error);
+                    None
+                }
+                "number_format" => {
+                    number_options.push(name.clone());
+                    let span = value.span(&name);
+                    modes.number_format = value.string(&name, "{n}| ", compile_error);
+                    if let Some(format) = &modes.number_format {
+                        if !format.contains("{n}") {
+                            compile_error(
+                                span,
+                                "expected `{n}` in the format, which is replaced with the number of the line",
+                            );
+                        }
+                    }
+                    None
+                }
+                "number_skip_blank" => {
+                    number_options.push(name.clone());
+                    modes.number_skip_blank = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "line_hash_comments" => {
+                    modes.line_hash_comments = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "diff_markers" => {
+                    let diff = match value {
+                        AttrValue::None => Some(Diff::Markers(" ".to_string())),
+                        value => value.string(&name, "+", compile_error).map(Diff::Markers),
+                    };
+                    modes.set_diff(&name, diff, compile_error);
+                    None
+                }
+                "as_diff" => {
+                    let diff = value
+                        .keyword(&name, &["added", "removed"], compile_error)
+                        .map(|index| Diff::All(if index == 0 { '+' } else { '-' }));
+                    modes.set_diff(&name, diff, compile_error);
+                    None
+                }
+                "delimiter" => {
+                    modes.delimiter = value
+                        .string(&name, ":", compile_error)
+                        .filter(|delimiter| {
+                            if delimiter.is_empty() {
+                                compile_error(name.span(), "the delimiter can't be empty");
+                            }
+                            !delimiter.is_empty()
+                        })
+                        .map(|delimiter| (name.span(), delimiter));
+                    None
+                }
+                "strict_keys" => {
+                    strict_keys_span = Some(name.span());
+                    modes.strict_keys = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "allow_empty" => {
+                    modes.allow_empty = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "check_braces" => {
+                    modes.check_braces = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "validate" => {
+                    if !cfg!(feature = "json") {
+                        compile_error(
+                            name.span(),
+                            "`#[validate = json]` requires the `json` feature of `docstr`",
+                        );
+                    }
+                    modes.validate_json = value.keyword(&name, &["json"], compile_error).is_some();
+                    None
+                }
+                "validate_no_trailing_whitespace" => {
+                    modes.validate_no_trailing_whitespace =
+                        value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "join" => {
+                    modes.join = value.string(&name, " ", compile_error);
+                    None
+                }
+                "repeat" => {
+                    modes.repeat = value.positive_integer(&name, compile_error);
+                    None
+                }
+                "nfc" | "nfd" => {
+                    if !cfg!(feature = "normalize") {
+                        compile_error(
+                            name.span(),
+                            &format!("`#[{name}]` requires the `normalize` feature of `docstr`"),
+                        );
+                    }
+                    let normalization = if name.to_string() == "nfc" {
+                        Normalization::Nfc
+                    } else {
+                        Normalization::Nfd
+                    };
+                    let normalization = value.flag(&name, compile_error).map(|()| normalization);
+                    modes.set_normalization(&name, normalization, compile_error);
+                    None
+                }
+                "upper" => {
+                    let case = value.flag(&name, compile_error).map(|()| Case::Upper);
+                    modes.set_case(&name, case, compile_error);
+                    None
+                }
+                "lower" => {
+                    let case = value.flag(&name, compile_error).map(|()| Case::Lower);
+                    modes.set_case(&name, case, compile_error);
+                    None
+                }
+                "leading_newline" => {
+                    modes.leading_newline = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "strip_final_newline" => {
+                    modes.strip_final_newline = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "ensure_shebang" => {
+                    modes.ensure_shebang = value.string(&name, "/bin/sh", compile_error);
+                    None
+                }
+                "emit_raw_bytes_hex_dump" => {
+                    modes.hex_dump = value.flag(&name, compile_error).is_some();
+                    None
+                }
+                "wrap_in" => {
+                    modes.wrap_in =
+                        value
+                            .strings(&name, &["open", "close"], compile_error)
+                            .map(|strings| {
+                                let [open, close] = <[String; 2]>::try_from(strings)
+                                    .expect("we asked for exactly 2 strings");
+                                (open, close)
+                            });
+                    None
+                }
+                _ => {
+                    compile_error(name.span(), &format!("unknown mode `{name}`"));
+                    None
+                }
+            };
+
+            if let Some(output) = output {
+                if let Some((previous, _)) = &modes.output {
+                    compile_error(
+                        name.span(),
+                        &format!("`#[{name}]` can't be used together with `#[{previous}]`"),
+                    );
+                } else {
+                    modes.output = Some((name, output));
+                }
+            }
+        }
+
+        // docstr!(#[dedent], format! ...)
+        //                  ^ optional
+        if !seen.is_empty()
+            && matches!(input.peek(), Some(TokenTree::Punct(comma)) if *comma == ',')
+        {
+            input.next();
+        }
+
+        if let Some(span) = hang_span {
+            if modes.wrap.is_none() {
+                compile_error(span, "`#[hang]` requires `#[wrap = N]`");
+            }
+        }
+        if !modes.number {
+            for name in number_options {
+                compile_error(name.span(), &format!("`#[{name}]` requires `#[number]`"));
+            }
+        }
+        if let Some(span) = dedent_first_span {
+            if modes.dedent {
+                compile_error(
+                    span,
+                    "`#[dedent_first]` can't be used together with `#[dedent]`",
+                );
+            }
+        }
+        if let Some(span) = wrap_preserve_indent_span {
+            if modes.wrap.is_none() {
+                compile_error(span, "`#[wrap_preserve_indent]` requires `#[wrap = N]`");
+            }
+        }
+        if let Some(span) = strict_keys_span {
+            if !matches!(modes.output, Some((_, Output::SortedMap(_)))) {
+                compile_error(
+                    span,
+                    "`#[strict_keys]` requires `#[emit_static_str_map_sorted = NAME]`",
+                );
+            }
+        }
+
+        modes
+    }
+
+    /// `#[upper]` and `#[lower]` contradict each other, so only 1 of them can be used
+    fn set_case(
+        &mut self,
+        name: &Ident,
+        case: Option<Case>,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) {
+        let previous = match self.case {
+            Some(Case::Upper) => "upper",
+            Some(Case::Lower) => "lower",
+            None => {
+                self.case = case;
+                return;
+            }
+        };
+        compile_error(
+            name.span(),
+            &format!("`#[{name}]` can't be used together with `#[{previous}]`"),
+        );
+    }
+
+    /// `#[nfc]` and `#[nfd]` contradict each other, so only 1 of them can be used
+    fn set_normalization(
+        &mut self,
+        name: &Ident,
+        normalization: Option<Normalization>,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) {
+        let previous = match self.normalization {
+            Some(Normalization::Nfc) => "nfc",
+            Some(Normalization::Nfd) => "nfd",
+            None => {
+                self.normalization = normalization;
+                return;
+            }
+        };
+        compile_error(
+            name.span(),
+            &format!("`#[{name}]` can't be used together with `#[{previous}]`"),
+        );
+    }
+
+    /// `#[diff_markers]` and `#[as_diff]` both prefix lines, so only 1 of them can be used
+    fn set_diff(
+        &mut self,
+        name: &Ident,
+        diff: Option<Diff>,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) {
+        let previous = match &self.diff {
+            Some(Diff::Markers(_)) => "diff_markers",
+            Some(Diff::All(_)) => "as_diff",
+            None => {
+                self.diff = diff;
+                return;
+            }
+        };
+        compile_error(
+            name.span(),
+            &format!("`#[{name}]` can't be used together with `#[{previous}]`"),
+        );
+    }
+}
+
+/// Everything after the name of the mode
+///
+/// ```ignore
+/// #[deflate = NAME]
+/// //        ^^^^^^
+/// ```
+enum AttrValue {
+    /// `#[mode]`
+    None,
+    /// `#[mode = ...]`
+    Eq(Vec<TokenTree>),
+    /// `#[mode(...)]`
+    Args(Group),
+    /// Anything else, which no mode accepts
+    Invalid(Span),
+}
+
+impl AttrValue {
+    /// Parse the tokens following the name of the mode
+    fn parse(mut tokens: token_stream::IntoIter) -> Self {
+        match tokens.next() {
+            None => Self::None,
+            Some(TokenTree::Punct(eq)) if eq == '=' => Self::Eq(tokens.collect()),
+            Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => {
+                match tokens.next() {
+                    None => Self::Args(args),
+                    Some(tt) => Self::Invalid(tt.span()),
+                }
+            }
+            Some(tt) => Self::Invalid(tt.span()),
+        }
+    }
+
+    /// Span of the value, or of the `name` if there is no value
+    fn span(&self, name: &Ident) -> Span {
+        match self {
+            Self::None => name.span(),
+            Self::Eq(tokens) => tokens.first().map_or_else(|| name.span(), TokenTree::span),
+            Self::Args(args) => args.span(),
+            Self::Invalid(span) => *span,
+        }
+    }
+
+    /// `#[mode = any tokens]`, where `example` is shown in the error message
+    fn tokens(
+        self,
+        name: &Ident,
+        example: &str,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<TokenStream> {
+        match self {
+            Self::Eq(tokens) if !tokens.is_empty() => Some(tokens.into_iter().collect()),
+            _ => {
+                compile_error(
+                    self.span(name),
+                    &format!("expected `#[{name} = {example}]`"),
+                );
+                None
+            }
+        }
+    }
+
+    /// `#[mode = "foo"]`, where `example` is shown in the error message
+    fn string(
+        self,
+        name: &Ident,
+        example: &str,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<String> {
+        let span = self.span(name);
+
+        if let Self::Eq(tokens) = self {
+            if let Ok([tt]) = <[TokenTree; 1]>::try_from(tokens) {
+                if let Ok(string) = litrs::StringLit::try_from(tt) {
+                    return Some(string.into_value());
+                }
+            }
+        }
+
+        compile_error(
+            span,
+            &format!("expected a string literal like `#[{name} = \"{example}\"]`"),
+        );
+        None
+    }
+
+    /// `#[mode("foo", "bar")]`, where `params` are names of each string
+    fn strings(
+        self,
+        name: &Ident,
+        params: &[&str],
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<Vec<String>> {
+        let expected = format!(
+            "expected `#[{name}({})]` with string literals",
+            params.join(", ")
+        );
+
+        let Self::Args(args) = &self else {
+            compile_error(self.span(name), &expected);
+            return None;
+        };
+
+        let mut strings = Vec::new();
+        let mut tokens = args.stream().into_iter();
+
+        loop {
+            // #[wrap_in("<!-- ", " -->")]
+            //           ^^^^^^^  ^^^^^^
+            match tokens.next() {
+                Some(tt) => {
+                    let span = tt.span();
+                    match litrs::StringLit::try_from(tt) {
+                        Ok(string) => strings.push(string.into_value()),
+                        Err(_) => {
+                            compile_error(span, &expected);
+                            return None;
+                        }
+                    }
+                }
+                None => break,
+            }
+
+            // #[wrap_in("<!-- ", " -->")]
+            //                  ^
+            match tokens.next() {
+                Some(TokenTree::Punct(comma)) if comma == ',' => (),
+                Some(tt) => {
+                    compile_error(tt.span(), &expected);
+                    return None;
+                }
+                None => break,
+            }
+        }
+
+        if strings.len() != params.len() {
+            compile_error(args.span(), &expected);
+            return None;
+        }
+
+        Some(strings)
+    }
+
+    /// `#[mode(NAME = "value", OTHER = "value")]`
+    fn substitutions(
+        self,
+        name: &Ident,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<Vec<(String, String)>> {
+        let expected = format!("expected `#[{name}(NAME = \"value\", ...)]`");
+
+        let Self::Args(args) = &self else {
+            compile_error(self.span(name), &expected);
+            return None;
+        };
+
+        let mut substitutions = Vec::<(String, String)>::new();
+        let mut tokens = args.stream().into_iter();
+
+        loop {
+            // #[subst(NAME = "value")]
+            //         ^^^^
+            let key = match tokens.next() {
+                Some(TokenTree::Ident(key)) => key,
+                Some(tt) => {
+                    compile_error(tt.span(), &expected);
+                    return None;
+                }
+                None => break,
+            };
+
+            // #[subst(NAME = "value")]
+            //              ^
+            match tokens.next() {
+                Some(TokenTree::Punct(eq)) if eq == '=' => (),
+                tt => {
+                    compile_error(tt.map_or(key.span(), |tt| tt.span()), &expected);
+                    return None;
+                }
+            }
+
+            // #[subst(NAME = "value")]
+            //                ^^^^^^^
+            let value = match tokens
+                .next()
+                .map(|tt| (tt.span(), litrs::StringLit::try_from(tt)))
+            {
+                Some((_, Ok(value))) => value.into_value(),
+                Some((span, Err(_))) => {
+                    compile_error(span, &expected);
+                    return None;
+                }
+                None => {
+                    compile_error(key.span(), &expected);
+                    return None;
+                }
+            };
+
+            let key_str = key.to_string();
+            if substitutions
+                .iter()
+                .any(|(existing, _)| *existing == key_str)
+            {
+                compile_error(
+                    key.span(),
+                    &format!("`{key}` is substituted more than once"),
+                );
+            } else {
+                substitutions.push((key_str, value));
+            }
+
+            // #[subst(NAME = "value", OTHER = "value")]
+            //                       ^
+            match tokens.next() {
+                Some(TokenTree::Punct(comma)) if comma == ',' => (),
+                Some(tt) => {
+                    compile_error(tt.span(), &expected);
+                    return None;
+                }
+                None => break,
+            }
+        }
+
+        Some(substitutions)
+    }
+
+    /// `#[mode]`
+    fn flag(self, name: &Ident, compile_error: &mut impl FnMut(Span, &str)) -> Option<()> {
+        match self {
+            Self::None => Some(()),
+            _ => {
+                compile_error(
+                    self.span(name),
+                    &format!("`#[{name}]` does not take a value"),
+                );
+                None
+            }
+        }
+    }
+
+    /// `#[mode = keyword]`, returns which of the `keywords` it is
+    fn keyword(
+        self,
+        name: &Ident,
+        keywords: &[&str],
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<usize> {
+        let span = self.span(name);
+
+        if let Self::Eq(tokens) = self {
+            if let Ok([TokenTree::Ident(keyword)]) = <[TokenTree; 1]>::try_from(tokens) {
+                let keyword = keyword.to_string();
+                if let Some(index) = keywords.iter().position(|k| *k == keyword) {
+                    return Some(index);
+                }
+            }
+        }
+
+        let expected = keywords
+            .iter()
+            .map(|keyword| format!("`#[{name} = {keyword}]`"))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        compile_error(span, &format!("expected {expected}"));
+        None
+    }
+
+    /// `#[mode = 4]` or `#[mode(4)]`, which is at least 1
+    fn positive_integer(
+        self,
+        name: &Ident,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<usize> {
+        self.integer(name, 1, compile_error)
+    }
+
+    /// `#[mode = 4]` or `#[mode(4)]`, which is at least `min`
+    fn integer(
+        self,
+        name: &Ident,
+        min: usize,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<usize> {
+        let expected = match min {
+            0 => format!("expected a non-negative integer like `#[{name} = 4]`"),
+            1 => format!("expected a positive integer like `#[{name} = 4]`"),
+            _ => format!("expected an integer of at least {min} like `#[{name} = {min}]`"),
+        };
+
+        let span = self.span(name);
+        let tokens = match self {
+            Self::Eq(tokens) => tokens,
+            Self::Args(args) => args.stream().into_iter().collect(),
+            _ => Vec::new(),
+        };
+        let integer = match <[TokenTree; 1]>::try_from(tokens) {
+            Ok([tt]) => litrs::IntegerLit::try_from(tt).ok(),
+            Err(_) => None,
+        };
+
+        match integer.and_then(|integer| integer.value::<usize>()) {
+            Some(integer) if integer >= min => Some(integer),
+            _ => {
+                compile_error(span, &expected);
+                None
+            }
+        }
+    }
+
+    /// `#[mode = 1..3]`, `#[mode = 1..=2]`, `#[mode = ..3]` or `#[mode = 1..]`
+    fn range(self, name: &Ident, compile_error: &mut impl FnMut(Span, &str)) -> Option<LineRange> {
+        let span = self.span(name);
+
+        let range = match &self {
+            Self::Eq(tokens) => parse_range(tokens),
+            _ => None,
+        };
+        let Some((start, end)) = range else {
+            compile_error(
+                span,
+                &format!("expected a range like `#[{name} = 1..3]` or `#[{name} = 1..=2]`"),
+            );
+            return None;
+        };
+
+        if end.map_or(false, |end| end < start) {
+            compile_error(span, "the range starts after it ends");
+            return None;
+        }
+
+        Some(LineRange { start, end, span })
+    }
+
+    /// `#[mode = pub NAME]`
+    fn item_name(
+        self,
+        name: &Ident,
+        compile_error: &mut impl FnMut(Span, &str),
+    ) -> Option<ItemName> {
+        let expected = format!("expected `#[{name} = NAME]`");
+
+        let mut tokens = match self {
+            Self::Eq(tokens) => tokens,
+            _ => {
+                compile_error(self.span(name), &expected);
+                return None;
+            }
+        };
+
+        // #[deflate = pub(crate) NAME]
+        //                        ^^^^
+        match tokens.pop() {
+            Some(TokenTree::Ident(ident)) => Some(ItemName {
+                vis: tokens.into_iter().collect(),
+                ident,
+            }),
+            Some(tt) => {
+                compile_error(tt.span(), "expected identifier");
+                None
+            }
+            None => {
+                compile_error(name.span(), &expected);
+                None
+            }
+        }
+    }
+}
+
+/// Parse the next mode attribute `#[mode = value]`, which can contain several modes `#[a, b = value]`
+///
+/// Returns `None` without consuming anything if the input doesn't start with a mode
+fn parse_attribute(
+    input: &mut Peekable<token_stream::IntoIter>,
+    compile_error: &mut impl FnMut(Span, &str),
+) -> Option<Vec<(Ident, AttrValue)>> {
+    // #[deflate = NAME]
+    // ^
+    match input.peek() {
+        Some(TokenTree::Punct(punct)) if *punct == '#' => (),
+        _ => return None,
+    }
+
+    // Look ahead without consuming, because if this turns out to be a
+    // doc comment then we want to leave it for the doc comment parser
+    let mut lookahead = input.clone();
+    lookahead.next();
+
+    // #[deflate = NAME]
+    //  ^^^^^^^^^^^^^^^^
+    let attr = match lookahead.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
+        _ => return None,
+    };
+    let mut attr_inner = attr.stream().into_iter();
+
+    // #[deflate = NAME]
+    //   ^^^^^^^
+    let name = match attr_inner.next() {
+        Some(TokenTree::Ident(name)) if name.to_string() != "doc" && name.to_string() != "cfg" => {
+            name
+        }
+        _ => return None,
+    };
+
+    // It is a mode, so consume `#` and `[...]`
+    input.next();
+    input.next();
+
+    // #[dedent, join = ", "]
+    //         ^ each `,` starts the next mode
+    let mut modes = Vec::new();
+    let mut name = Some(name);
+    let mut value = Vec::new();
+    for tt in attr_inner.chain([TokenTree::Punct(Punct::new(',', Spacing::Alone))]) {
+        match (tt, &name) {
+            (TokenTree::Punct(comma), Some(_)) if comma == ',' => {
+                let name = name.take().expect("matched `Some`");
+                let value = TokenStream::from_iter(value.drain(..)).into_iter();
+                modes.push((name, AttrValue::parse(value)));
+            }
+            (tt, Some(_)) => value.push(tt),
+            (TokenTree::Ident(next), None) => name = Some(next),
+            // #[dedent, ]
+            //         ^ trailing comma
+            (TokenTree::Punct(comma), None) if comma == ',' => (),
+            (tt, None) => {
+                compile_error(tt.span(), "expected the name of a mode");
+                break;
+            }
+        }
+    }
+
+    Some(modes)
+}
+
+/// Parse the short flags `[d, t]` into the modes they stand for, see [`SHORT_FLAGS`]
+fn parse_short_flags(
+    group: &Group,
+    compile_error: &mut impl FnMut(Span, &str),
+) -> Vec<(Ident, AttrValue)> {
+    let mut modes = Vec::new();
+    let mut expect_flag = true;
+
+    for tt in group.stream() {
+        match tt {
+            TokenTree::Punct(comma) if comma == ',' && !expect_flag => expect_flag = true,
+            TokenTree::Ident(flag) if expect_flag => {
+                expect_flag = false;
+                let flag_str = flag.to_string();
+                match SHORT_FLAGS.iter().find(|(short, _)| *short == flag_str) {
+                    // `d` is reported as `#[dedent]` in all other errors
+                    Some((_, mode)) => {
+                        modes.push((Ident::new(mode, flag.span()), AttrValue::None));
+                    }
+                    None => {
+                        let expected = SHORT_FLAGS
+                            .iter()
+                            .map(|(short, mode)| format!("- `{short}` for `#[{mode}]`"))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        compile_error(
+                            flag.span(),
+                            &format!("unknown short flag `{flag}`, expected one of:\n{expected}"),
+                        );
+                    }
+                }
+            }
+            tt => {
+                let expected = if expect_flag { "a short flag" } else { "`,`" };
+                compile_error(tt.span(), &format!("expected {expected}"));
+                break;
+            }
+        }
+    }
+
+    modes
+}
+
+/// Parse `1..3`, `1..=2`, `..3` or `1..` into its start and exclusive end
+fn parse_range(tokens: &[TokenTree]) -> Option<(usize, Option<usize>)> {
+    let integer = |tt: &TokenTree| match tt {
+        TokenTree::Literal(literal) => litrs::IntegerLit::try_from(literal.clone())
+            .ok()?
+            .value::<usize>(),
+        _ => None,
+    };
+    let is_punct = |tt: &TokenTree, ch: char| matches!(tt, TokenTree::Punct(punct) if *punct == ch);
+
+    // 1..=3
+    // ^
+    let (start, tokens) = match tokens {
+        [first @ TokenTree::Literal(_), rest @ ..] => (integer(first)?, rest),
+        _ => (0, tokens),
+    };
+
+    // 1..=3
+    //  ^^^
+    let (inclusive, tokens) = match tokens {
+        [a, b, eq, rest @ ..] if is_punct(a, '.') && is_punct(b, '.') && is_punct(eq, '=') => {
+            (true, rest)
+        }
+        [a, b, rest @ ..] if is_punct(a, '.') && is_punct(b, '.') => (false, rest),
+        _ => return None,
+    };
+
+    // 1..=3
+    //     ^
+    match tokens {
+        [] if !inclusive => Some((start, None)),
+        [last] => {
+            let end = integer(last)?;
+            Some((
+                start,
+                Some(if inclusive { end.checked_add(1)? } else { end }),
+            ))
+        }
+        _ => None,
+    }
+}
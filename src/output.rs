@@ -0,0 +1,840 @@
+//! Generates everything other than a string, for modes in [`Output`](crate::Output)
+
+use crate::{Docstr, ItemName};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Items generated by `#[deflate = NAME]`
+///
+/// ```ignore
+/// const NAME: &[u8] = b"...";
+/// const NAME_LEN: usize = 100;
+/// ```
+pub fn deflate_items(name: &ItemName, string: &str) -> TokenStream {
+    let len_name = ItemName {
+        vis: name.vis.clone(),
+        ident: Ident::new(&format!("{}_LEN", name.ident), name.ident.span()),
+    };
+
+    TokenStream::from_iter(
+        const_item(
+            name,
+            "&[u8]",
+            [TokenTree::Literal(Literal::byte_string(&deflate(string)))],
+        )
+        .into_iter()
+        .chain(const_item(
+            &len_name,
+            "usize",
+            [TokenTree::Literal(Literal::usize_unsuffixed(string.len()))],
+        )),
+    )
+}
+
+/// Compress the string with raw DEFLATE
+#[cfg(feature = "compress")]
+fn deflate(string: &str) -> Vec<u8> {
+    use std::io::Write as _;
+
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(string.as_bytes())
+        .expect("writing to a `Vec` can't fail");
+    encoder.finish().expect("writing to a `Vec` can't fail")
+}
+
+/// Without the `compress` feature, `#[deflate]` is always a compile error
+/// so this is never reached
+#[cfg(not(feature = "compress"))]
+fn deflate(_: &str) -> Vec<u8> {
+    unreachable!("`#[deflate]` without the `compress` feature is a compile error")
+}
+
+/// Item generated by `#[emit_const_str_slice_of_bytes = NAME]`
+///
+/// ```ignore
+/// const NAME: &[u8] = &[0x48, 0x69];
+/// ```
+pub fn byte_slice_item(name: &ItemName, string: &str) -> TokenStream {
+    let mut bytes = TokenStream::new();
+    for byte in string.bytes() {
+        bytes.extend([
+            TokenTree::Literal(
+                format!("0x{byte:02X}")
+                    .parse()
+                    .expect("hex integer is a valid literal"),
+            ),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    const_item(
+        name,
+        "&[u8]",
+        [
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, bytes)),
+        ],
+    )
+}
+
+/// Item generated by `#[to_const_option = NAME]`
+///
+/// ```ignore
+/// const NAME: Option<&str> = Some("...");
+/// const NAME: Option<&str> = None;
+/// ```
+pub fn option_item(name: &ItemName, string: &str) -> TokenStream {
+    let value = if string.is_empty() {
+        "::core::option::Option::None"
+            .parse::<TokenStream>()
+            .expect("path is valid")
+    } else {
+        let mut some = "::core::option::Option::Some"
+            .parse::<TokenStream>()
+            .expect("path is valid");
+        some.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(Literal::string(string)).into(),
+        ))]);
+        some
+    };
+
+    const_item(name, "::core::option::Option<&str>", value)
+}
+
+/// Item generated by `#[emit_const_array_2d = NAME]`
+///
+/// ```ignore
+/// const NAME: &[&[&str]] = &[&["a", "b"], &["c", ""]];
+/// ```
+pub fn array_2d_item(name: &ItemName, string: &str) -> TokenStream {
+    let rows = string
+        .split('\n')
+        // Blank lines can be used to group rows
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            // | a | b |
+            // ^       ^ optional
+            let line = line.trim();
+            let line = line.strip_prefix('|').unwrap_or(line);
+            let line = line.strip_suffix('|').unwrap_or(line);
+            line.split('|').map(str::trim).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Ragged rows are padded with empty cells
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut array = TokenStream::new();
+    for row in rows {
+        let mut cells = TokenStream::new();
+        for column in 0..columns {
+            cells.extend([
+                TokenTree::Literal(Literal::string(row.get(column).copied().unwrap_or(""))),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+            ]);
+        }
+        array.extend([
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, cells)),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    const_item(
+        name,
+        "&[&[&str]]",
+        [
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, array)),
+        ],
+    )
+}
+
+/// Item generated by `#[measure_const = NAME]`
+///
+/// ```ignore
+/// const NAME: (usize, usize) = (columns, rows);
+/// ```
+pub fn measure_item(name: &ItemName, string: &str) -> TokenStream {
+    let columns = string
+        .split('\n')
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let rows = string.split('\n').count();
+
+    const_item(
+        name,
+        "(usize, usize)",
+        [TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([
+                TokenTree::Literal(Literal::usize_unsuffixed(columns)),
+                TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                TokenTree::Literal(Literal::usize_unsuffixed(rows)),
+            ]),
+        ))],
+    )
+}
+
+/// Item generated by `#[emit_static_str_map_sorted = NAME]`
+///
+/// ```ignore
+/// docstr!(#[emit_static_str_map_sorted = NAME]
+///     /// b: 2
+///     /// a: 1
+/// )
+/// ```
+///
+/// Expands to:
+///
+/// ```ignore
+/// const NAME: &[(&str, &str)] = &[("a", "1"), ("b", "2")];
+/// ```
+pub fn sorted_map_item(
+    docstr: &Docstr,
+    name: &ItemName,
+    compile_error: &mut dyn FnMut(Span, &str),
+) -> TokenStream {
+    let mut pairs = key_value_pairs(docstr, ":", compile_error);
+
+    // Stable, so pairs with the same key keep their order
+    pairs.sort_by_key(|(key, _, _)| *key);
+
+    if docstr.modes.strict_keys {
+        for window in pairs.windows(2) {
+            let [(previous, _, _), (key, _, span)] = window else {
+                unreachable!("windows of 2 always have 2 elements")
+            };
+            if previous == key {
+                compile_error(*span, &format!("key `{key}` is used more than once"));
+            }
+        }
+    }
+
+    const_item(name, "&[(&str, &str)]", pairs_slice(&pairs))
+}
+
+/// Expression generated by `docstr_map!`, with the pairs in the order they were written
+///
+/// ```ignore
+/// &[("key 1", "value 1"), ("key 2", "value 2")]
+/// ```
+pub fn map(docstr: &Docstr, compile_error: &mut dyn FnMut(Span, &str)) -> TokenStream {
+    pairs_slice(&key_value_pairs(docstr, "=", compile_error))
+}
+
+/// Split each line on the `#[delimiter]`, or on `default_delimiter`. Keys and values are trimmed
+fn key_value_pairs<'a>(
+    docstr: &'a Docstr,
+    default_delimiter: &str,
+    compile_error: &mut dyn FnMut(Span, &str),
+) -> Vec<(&'a str, &'a str, Span)> {
+    let delimiter = docstr
+        .modes
+        .delimiter
+        .as_ref()
+        .map_or(default_delimiter, |(_, delimiter)| delimiter);
+
+    let mut pairs = Vec::new();
+
+    for doc_comment in &docstr.doc_comments {
+        // Blank lines can be used to group pairs
+        if doc_comment.text.trim().is_empty() {
+            continue;
+        }
+
+        let Some((key, value)) = doc_comment.text.split_once(delimiter) else {
+            compile_error(
+                doc_comment.span,
+                &if delimiter == ":" {
+                    "expected `key: value`".to_string()
+                } else {
+                    format!("expected `key{delimiter}value`, there is no `{delimiter}`")
+                },
+            );
+            continue;
+        };
+
+        pairs.push((key.trim(), value.trim(), doc_comment.span));
+    }
+
+    pairs
+}
+
+/// ```ignore
+/// &[("key 1", "value 1"), ("key 2", "value 2")]
+/// ```
+fn pairs_slice(pairs: &[(&str, &str, Span)]) -> TokenStream {
+    let mut slice = TokenStream::new();
+    for (key, value, _) in pairs {
+        slice.extend([
+            TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                TokenStream::from_iter([
+                    TokenTree::Literal(Literal::string(key)),
+                    TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                    TokenTree::Literal(Literal::string(value)),
+                ]),
+            )),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    TokenStream::from_iter([
+        TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, slice)),
+    ])
+}
+
+/// Item generated by `#[emit_const_u8_slices_per_line = NAME]`
+///
+/// ```ignore
+/// const NAME: &[&[u8]] = &[b"line 1", b"", b"line 3"];
+/// ```
+pub fn byte_lines_item(name: &ItemName, string: &str) -> TokenStream {
+    let mut lines = TokenStream::new();
+    for line in string.split('\n') {
+        lines.extend([
+            TokenTree::Literal(Literal::byte_string(line.as_bytes())),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    const_item(
+        name,
+        "&[&[u8]]",
+        [
+            TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, lines)),
+        ],
+    )
+}
+
+/// Items generated by `#[named = NAME]`
+///
+/// ```ignore
+/// const NAME: &str = "...";
+/// #[doc(hidden)]
+/// #[allow(dead_code)]
+/// const __DOCSTR_NAMED_CBF29CE484222325: () = ();
+/// ```
+///
+/// The 2nd item is named after the hash of the string, so defining it twice in the same module is an
+/// error from the compiler, even if `NAME` is different.
+pub fn named_items(name: &ItemName, string: &str) -> TokenStream {
+    let mut items = const_item(name, "&str", [TokenTree::Literal(Literal::string(string))]);
+
+    let guard = ItemName {
+        vis: TokenStream::new(),
+        ident: Ident::new(
+            &format!("__DOCSTR_NAMED_{:016X}", crate::transform::fnv1a(string)),
+            name.ident.span(),
+        ),
+    };
+    items.extend(
+        "#[doc(hidden)] #[allow(dead_code)]"
+            .parse::<TokenStream>()
+            .expect("attributes are valid"),
+    );
+    items.extend(const_item(
+        &guard,
+        "()",
+        [TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::new(),
+        ))],
+    ));
+
+    items
+}
+
+/// Slice generated by `#[chunk_lines(N)]`
+///
+/// ```ignore
+/// &["line 1\nline 2", "line 3"]
+/// ```
+pub fn chunk_lines(string: &str, lines_per_chunk: usize) -> TokenStream {
+    let lines = string.split('\n').collect::<Vec<_>>();
+
+    let mut chunks = TokenStream::new();
+    for chunk in lines.chunks(lines_per_chunk) {
+        chunks.extend([
+            TokenTree::Literal(Literal::string(&chunk.join("\n"))),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    TokenStream::from_iter([
+        TokenTree::Punct(Punct::new('&', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Bracket, chunks)),
+    ])
+}
+
+/// Tokens generated by `#[emit_match_arms]`, and `#[emit_byte_match]` when `byte_patterns` is set
+///
+/// ```ignore
+/// docstr!(#[emit_match_arms] x
+///     /// "a" => 1
+///     /// _ => 0
+/// )
+/// ```
+///
+/// Expands to:
+///
+/// ```ignore
+/// match x {
+///     "a" => 1,
+///     _ => 0,
+/// }
+/// ```
+pub fn match_arms(
+    docstr: &Docstr,
+    mode: &Ident,
+    byte_patterns: bool,
+    compile_error: &mut dyn FnMut(Span, &str),
+) -> TokenStream {
+    if docstr.before.is_empty() {
+        compile_error(
+            Span::call_site(),
+            &format!("`#[{mode}]` expects an expression to match on before the doc comments"),
+        );
+    }
+    if !docstr.after.is_empty() {
+        compile_error(
+            Span::call_site(),
+            &format!("`#[{mode}]` does not accept any tokens after the doc comments"),
+        );
+    }
+
+    let mut arms = TokenStream::new();
+
+    for doc_comment in &docstr.doc_comments {
+        // Blank lines can be used to group arms
+        if doc_comment.text.trim().is_empty() {
+            continue;
+        }
+
+        let Some(arm) = parse_tokens(&doc_comment.text) else {
+            compile_error(doc_comment.span, "this match arm contains invalid tokens");
+            continue;
+        };
+
+        if byte_patterns {
+            if let Err(literal) = expect_byte_pattern(&arm) {
+                compile_error(
+                    doc_comment.span,
+                    &format!("expected a byte like `0x01` or `b'a'`, found `{literal}`"),
+                );
+                continue;
+            }
+        }
+
+        let ends_with_comma = matches!(
+            arm.clone().into_iter().last(),
+            Some(TokenTree::Punct(comma)) if comma == ','
+        );
+
+        arms.extend(arm);
+        if !ends_with_comma {
+            arms.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        }
+    }
+
+    TokenStream::from_iter(
+        [TokenTree::Ident(Ident::new("match", Span::call_site()))]
+            .into_iter()
+            .chain(docstr.before.clone())
+            .chain([TokenTree::Group(Group::new(Delimiter::Brace, arms))]),
+    )
+}
+
+/// Item generated by `#[emit_enum_variants(derive(Debug))]`
+///
+/// ```ignore
+/// docstr!(#[emit_enum_variants(derive(Debug))] pub enum Color
+///     /// Red
+///     /// Green
+/// )
+/// ```
+///
+/// Expands to:
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// pub enum Color {
+///     Red,
+///     Green,
+/// }
+/// ```
+pub fn enum_item(
+    docstr: &Docstr,
+    attributes: &TokenStream,
+    compile_error: &mut dyn FnMut(Span, &str),
+) -> TokenStream {
+    if docstr.before.is_empty() {
+        compile_error(
+            Span::call_site(),
+            "`#[emit_enum_variants]` expects the enum like `pub enum Name` before the doc comments",
+        );
+    }
+    if !docstr.after.is_empty() {
+        compile_error(
+            Span::call_site(),
+            "`#[emit_enum_variants]` does not accept any tokens after the doc comments",
+        );
+    }
+
+    let mut item = TokenStream::new();
+
+    // #[emit_enum_variants(derive(Debug), repr(u8))]
+    //                      ^^^^^^^^^^^^^  ^^^^^^^^ each becomes an attribute
+    let mut attribute = TokenStream::new();
+    for tt in attributes
+        .clone()
+        .into_iter()
+        .chain([TokenTree::Punct(Punct::new(',', Spacing::Alone))])
+    {
+        match tt {
+            TokenTree::Punct(comma) if comma == ',' => {
+                if !attribute.is_empty() {
+                    item.extend([
+                        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+                        TokenTree::Group(Group::new(
+                            Delimiter::Bracket,
+                            std::mem::take(&mut attribute),
+                        )),
+                    ]);
+                }
+            }
+            tt => attribute.extend([tt]),
+        }
+    }
+
+    item.extend(docstr.before.clone());
+
+    let mut variants = TokenStream::new();
+    for doc_comment in &docstr.doc_comments {
+        // Blank lines can be used to group variants
+        if doc_comment.text.trim().is_empty() {
+            continue;
+        }
+
+        let variant = parse_tokens(&doc_comment.text)
+            .map(|tokens| tokens.into_iter().collect::<Vec<_>>())
+            .and_then(|tokens| <[TokenTree; 1]>::try_from(tokens).ok());
+        let Some([TokenTree::Ident(mut variant)]) = variant else {
+            compile_error(
+                doc_comment.span,
+                &format!(
+                    "expected an identifier for the enum variant, found `{}`",
+                    doc_comment.text.trim()
+                ),
+            );
+            continue;
+        };
+
+        // Errors such as duplicate variants point at the line
+        variant.set_span(doc_comment.span);
+        variants.extend([
+            TokenTree::Ident(variant),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    item.extend([TokenTree::Group(Group::new(Delimiter::Brace, variants))]);
+    item
+}
+
+/// Expression generated by `#[extend = buf]`
+///
+/// ```ignore
+/// (buf).push_str("...")
+/// ```
+pub fn method_call(target: &TokenStream, method: &str, argument: TokenTree) -> TokenStream {
+    TokenStream::from_iter([
+        // So the method is called on the whole target like `&mut buf`, and not just `buf`
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, target.clone())),
+        TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(method, Span::call_site())),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, argument.into())),
+    ])
+}
+
+/// `#[with_location]`: prefix the literal with the file and line of the invocation
+///
+/// ```ignore
+/// ::core::concat!(::core::file!(), ":", ::core::line!(), " ", "foo\nbar")
+/// ```
+pub fn with_location(literal: Literal) -> TokenStream {
+    let mut arguments = "::core::file!(), \":\", ::core::line!(), \" \","
+        .parse::<TokenStream>()
+        .expect("arguments are valid");
+    arguments.extend([TokenTree::Literal(literal)]);
+
+    let mut concat = "::core::concat!"
+        .parse::<TokenStream>()
+        .expect("path is valid");
+    concat.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        arguments,
+    ))]);
+    concat
+}
+
+/// Expression generated by `#[join_expr = sep]`
+///
+/// ```ignore
+/// (["line 1", "line 2"]).join(sep)
+/// ```
+pub fn join_expr(string: &str, separator: &TokenStream) -> TokenStream {
+    let mut lines = TokenStream::new();
+    for line in string.split('\n') {
+        lines.extend([
+            TokenTree::Literal(Literal::string(line)),
+            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        ]);
+    }
+
+    method_call(
+        &TokenTree::Group(Group::new(Delimiter::Bracket, lines)).into(),
+        "join",
+        // Invisible, so `sep` is passed as a single expression without extra parentheses
+        TokenTree::Group(Group::new(Delimiter::None, separator.clone())),
+    )
+}
+
+/// Block generated by `#[emit_writeln_sequence]`
+///
+/// ```ignore
+/// docstr!(#[emit_writeln_sequence] w,
+///     /// line 1
+///     /// line 2
+/// )
+/// ```
+///
+/// Expands to:
+///
+/// ```ignore
+/// {
+///     ::core::writeln!(w, "line 1")?;
+///     ::core::writeln!(w, "line 2")?;
+/// }
+/// ```
+pub fn writeln_sequence(
+    docstr: &Docstr,
+    string: &str,
+    compile_error: &mut dyn FnMut(Span, &str),
+) -> TokenStream {
+    // w,
+    //  ^ optional
+    let mut writer = docstr.before.clone().into_iter().collect::<Vec<_>>();
+    if matches!(writer.last(), Some(TokenTree::Punct(comma)) if *comma == ',') {
+        writer.pop();
+    }
+
+    if writer.is_empty() {
+        compile_error(
+            Span::call_site(),
+            "`#[emit_writeln_sequence]` expects the writer before the doc comments",
+        );
+    }
+    if !docstr.after.is_empty() {
+        compile_error(
+            Span::call_site(),
+            "`#[emit_writeln_sequence]` does not accept any tokens after the doc comments",
+        );
+    }
+
+    let mut block = TokenStream::new();
+
+    for line in string.split('\n') {
+        block.extend(
+            "::core::writeln!"
+                .parse::<TokenStream>()
+                .expect("path is valid"),
+        );
+        block.extend([
+            TokenTree::Group(Group::new(
+                Delimiter::Parenthesis,
+                writer
+                    .iter()
+                    .cloned()
+                    .chain([
+                        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+                        TokenTree::Literal(Literal::string(line)),
+                    ])
+                    .collect(),
+            )),
+            TokenTree::Punct(Punct::new('?', Spacing::Alone)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+
+    TokenStream::from_iter([TokenTree::Group(Group::new(Delimiter::Brace, block))])
+}
+
+/// Check that every literal in the pattern of the match arm is a `u8`, or return the first one that isn't
+///
+/// ```ignore
+/// 0x01 | b'a' | 100..=200 => "ok"
+/// ^^^^^^^^^^^^^^^^^^^^^^^ pattern
+/// ```
+fn expect_byte_pattern(arm: &TokenStream) -> Result<(), String> {
+    let mut tokens = arm.clone().into_iter().peekable();
+
+    while let Some(tt) = tokens.next() {
+        match tt {
+            // 0x01 => "ok"
+            //      ^^ end of the pattern
+            TokenTree::Punct(eq) if eq == '=' && eq.spacing() == Spacing::Joint => {
+                if matches!(tokens.peek(), Some(TokenTree::Punct(gt)) if *gt == '>') {
+                    break;
+                }
+            }
+            // Bytes can't be negative
+            TokenTree::Punct(minus) if minus == '-' => {
+                let number = tokens.next().map(|tt| tt.to_string()).unwrap_or_default();
+                return Err(format!("{minus}{number}"));
+            }
+            TokenTree::Literal(literal) => {
+                let is_byte = match litrs::Literal::from(literal.clone()) {
+                    litrs::Literal::Byte(_) => true,
+                    litrs::Literal::Integer(integer) => {
+                        matches!(integer.suffix(), "" | "u8") && integer.value::<u8>().is_some()
+                    }
+                    _ => false,
+                };
+                if !is_byte {
+                    return Err(literal.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a line of Rust code into tokens.
+///
+/// `str::parse::<TokenStream>` panics when delimiters are unbalanced instead of
+/// returning an error, so we check that first
+pub fn parse_tokens(line: &str) -> Option<TokenStream> {
+    let mut delimiters = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' | '[' | '{' => delimiters.push(ch),
+            ')' | ']' | '}' => {
+                let open = match ch {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if delimiters.pop() != Some(open) {
+                    return None;
+                }
+            }
+            // "foo \" bar"
+            '"' => loop {
+                match chars.next()? {
+                    '\\' => {
+                        chars.next()?;
+                    }
+                    '"' => break,
+                    _ => (),
+                }
+            },
+            // 'a' or '\n' are chars, but 'a is a lifetime
+            '\'' => {
+                let mut lookahead = chars.clone();
+                match lookahead.next() {
+                    Some('\\') => {
+                        chars.next();
+                        chars.next();
+                        while chars.next()? != '\'' {}
+                    }
+                    Some(_) if lookahead.next() == Some('\'') => {
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !delimiters.is_empty() {
+        return None;
+    }
+
+    line.parse().ok()
+}
+
+/// Raw string literal `r#"..."#` generated by `#[raw]`, with as few `#` as possible
+///
+/// Raw strings can't contain a `\r`, then it is `None`
+pub fn raw_string(string: &str) -> Option<Literal> {
+    if string.contains('\r') {
+        return None;
+    }
+
+    // The string can't contain `"` followed by as many `#` as the literal has,
+    // because that would end the literal
+    let hashes = string
+        .match_indices('"')
+        .map(|(index, _)| {
+            string[index + 1..]
+                .bytes()
+                .take_while(|&byte| byte == b'#')
+                .count()
+                + 1
+        })
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(hashes);
+
+    format!("r{hashes}\"{string}\"{hashes}").parse().ok()
+}
+
+/// Generates `$vis const $ident: $ty = $value;`
+pub fn const_item(
+    name: &ItemName,
+    ty: &str,
+    value: impl IntoIterator<Item = TokenTree>,
+) -> TokenStream {
+    item("const", name, ty, value)
+}
+
+/// Generates `$vis static $ident: $ty = $value;`
+pub fn static_item(
+    name: &ItemName,
+    ty: &str,
+    value: impl IntoIterator<Item = TokenTree>,
+) -> TokenStream {
+    item("static", name, ty, value)
+}
+
+/// Generates `$vis $keyword $ident: $ty = $value;`
+fn item(
+    keyword: &str,
+    name: &ItemName,
+    ty: &str,
+    value: impl IntoIterator<Item = TokenTree>,
+) -> TokenStream {
+    let mut item = name.vis.clone();
+    item.extend([
+        TokenTree::Ident(Ident::new(keyword, name.ident.span())),
+        TokenTree::Ident(name.ident.clone()),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+    ]);
+    item.extend(ty.parse::<TokenStream>().expect("type is valid"));
+    item.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+    item.extend(value);
+    item.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+    item
+}
@@ -0,0 +1,686 @@
+//! Transformations of the doc comments, which are enabled by [`Modes`](crate::Modes)
+
+use crate::modes::Normalization;
+
+/// `#[tabs = N]`: expand every tab to spaces, up to the next tab stop.
+///
+/// Tab stops are every `tab_width` columns, so `"ab\tc"` with a width of 4 becomes `"ab  c"`
+pub fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for ch in line.chars() {
+        if ch == '\t' {
+            let spaces = tab_width - column % tab_width;
+            expanded.extend(std::iter::repeat(' ').take(spaces));
+            column += spaces;
+        } else {
+            expanded.push(ch);
+            column += 1;
+        }
+    }
+
+    expanded
+}
+
+/// `#[subst(NAME = "value")]`: replace each `{NAME}` with `value`
+///
+/// Only identifiers in braces are placeholders, and `{{` is never the start of one.
+/// Returns the placeholders which don't have a substitution, they are kept as-is
+pub fn substitute<'line>(
+    line: &'line str,
+    substitutions: &[(String, String)],
+) -> (String, Vec<&'line str>) {
+    let mut substituted = String::with_capacity(line.len());
+    let mut unknown = Vec::new();
+    let mut rest = line;
+
+    while let Some(open) = rest.find('{') {
+        substituted.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        // {{NAME}}
+        // ^^ escaped, so not a placeholder
+        if rest.starts_with("{{") {
+            substituted.push_str("{{");
+            rest = &rest[2..];
+            continue;
+        }
+
+        // {NAME}
+        //  ^^^^
+        let name = rest[1..]
+            .split('}')
+            .next()
+            .filter(|name| rest[1 + name.len()..].starts_with('}') && is_identifier(name));
+
+        match name {
+            Some(name) => {
+                match substitutions.iter().find(|(key, _)| key == name) {
+                    Some((_, value)) => substituted.push_str(value),
+                    None => {
+                        unknown.push(name);
+                        substituted.push_str(&rest[..name.len() + 2]);
+                    }
+                }
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                substituted.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    substituted.push_str(rest);
+
+    (substituted, unknown)
+}
+
+/// Whether `s` is a valid Rust identifier, like `foo_1` but not `1foo` or `foo bar`
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or(false, |ch| ch.is_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+/// `#[strip_doc_links]`: replace each Markdown link with its text
+///
+/// - `[text](url)` becomes `text`
+/// - `[text][reference]` becomes `text`
+/// - `[text]` becomes `text`
+///
+/// If the text is entirely surrounded by backticks, they are removed too: `` [`Type`] `` becomes `Type`.
+/// A `[` without a matching `]` is kept as-is.
+pub fn strip_doc_links(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(open) = rest.find('[') {
+        stripped.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        // [text](url)
+        // ^^^^^^
+        let Some(text_len) = bracketed_len(rest, '[', ']') else {
+            // Not a link, so keep the `[`
+            stripped.push('[');
+            rest = &rest[1..];
+            continue;
+        };
+        let text = &rest[1..text_len - 1];
+        rest = &rest[text_len..];
+
+        // [text](url)
+        //       ^^^^^
+        //
+        // [text][reference]
+        //       ^^^^^^^^^^^
+        let target_len = match rest.chars().next() {
+            Some('(') => bracketed_len(rest, '(', ')'),
+            Some('[') => bracketed_len(rest, '[', ']'),
+            _ => None,
+        };
+        rest = &rest[target_len.unwrap_or(0)..];
+
+        let text = text
+            .strip_prefix('`')
+            .and_then(|text| text.strip_suffix('`'))
+            .unwrap_or(text);
+        stripped.push_str(text);
+    }
+
+    stripped.push_str(rest);
+
+    stripped
+}
+
+/// Length in bytes of `open ... close` at the start of `s`, including both delimiters.
+///
+/// Nested delimiters must be balanced. `None` if `s` doesn't start with `open`, or it is never closed
+fn bracketed_len(s: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0_usize;
+
+    for (index, ch) in s.char_indices() {
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth = depth.checked_sub(1)?;
+            if depth == 0 {
+                return Some(index + ch.len_utf8());
+            }
+        } else if index == 0 {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// `#[placeholder_style = printf]`: convert printf-style placeholders to Rust's format placeholders
+///
+/// - `%x`, `%X`, `%o`, `%e`, `%E` and `%p` become `{:x}`, `{:X}`, `{:o}`, `{:e}`, `{:E}` and `{:p}`
+/// - `%d`, `%i`, `%u`, `%s`, `%c`, `%f`, `%F`, `%g` and `%G` become `{}`
+/// - `%%` becomes `%`
+/// - `{` and `}` become `{{` and `}}`
+///
+/// Flags, width, precision and length modifiers like in `%-08.3lf` are dropped.
+/// On an unsupported placeholder, returns it as the error
+pub fn printf_to_format(line: &str) -> Result<String, String> {
+    let mut formatted = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '{' => formatted.push_str("{{"),
+            '}' => formatted.push_str("}}"),
+            '%' => {
+                // %%
+                if chars.next_if(|(_, ch)| *ch == '%').is_some() {
+                    formatted.push('%');
+                    continue;
+                }
+
+                // %-08.3lf
+                //  ^^^^^^^ dropped
+                while chars
+                    .next_if(|(_, ch)| "-+ #0123456789.hlLqjzt".contains(*ch))
+                    .is_some()
+                {}
+
+                // %-08.3lf
+                //        ^
+                let placeholder = match chars.next() {
+                    Some((_, 'x')) => "{:x}",
+                    Some((_, 'X')) => "{:X}",
+                    Some((_, 'o')) => "{:o}",
+                    Some((_, 'e')) => "{:e}",
+                    Some((_, 'E')) => "{:E}",
+                    Some((_, 'p')) => "{:p}",
+                    Some((_, 'd' | 'i' | 'u' | 's' | 'c' | 'f' | 'F' | 'g' | 'G')) => "{}",
+                    Some((index, ch)) => return Err(line[start..index + ch.len_utf8()].to_string()),
+                    None => return Err(line[start..].to_string()),
+                };
+                formatted.push_str(placeholder);
+            }
+            ch => formatted.push(ch),
+        }
+    }
+
+    Ok(formatted)
+}
+
+/// `#[brace_unescape]`: turn `{{` into `{` and `}}` into `}`, like `format!` does
+///
+/// On a `{` or `}` which isn't escaped, returns it as the error
+pub fn unescape_braces(line: &str) -> Result<String, char> {
+    let mut unescaped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        // The 2nd brace of `{{` is skipped
+        if (ch == '{' || ch == '}') && chars.next() != Some(ch) {
+            return Err(ch);
+        }
+        unescaped.push(ch);
+    }
+
+    Ok(unescaped)
+}
+
+/// `#[unescape]`: turn the escapes of a byte string literal, like `\xFF`, into the bytes
+///
+/// On an invalid escape, returns a message describing it as the error
+pub fn unescape_bytes(string: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(string.len());
+    let mut chars = string.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => b'\0',
+            Some('\\') => b'\\',
+            Some('\'') => b'\'',
+            Some('"') => b'"',
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    // `from_str_radix` also accepts a sign like `+F`
+                    Ok(byte)
+                        if digits.len() == 2 && digits.chars().all(|ch| ch.is_ascii_hexdigit()) =>
+                    {
+                        byte
+                    }
+                    _ => {
+                        return Err(format!(
+                            "invalid escape `\\x{digits}`, expected 2 hex digits like `\\xFF`"
+                        ))
+                    }
+                }
+            }
+            Some(ch) => return Err(format!("unknown escape `\\{ch}`")),
+            None => {
+                return Err("expected an escape after `\\`, use `\\\\` for a backslash".to_string())
+            }
+        };
+        bytes.push(byte);
+    }
+
+    Ok(bytes)
+}
+
+/// `docstr_paragraphs!`: split the string into paragraphs, which are separated by blank lines
+///
+/// Lines inside of a paragraph are joined with `\n`. Blank lines at the start and end are ignored
+pub fn paragraphs(string: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = Vec::new();
+
+    for line in string.split('\n') {
+        if !line.trim().is_empty() {
+            paragraph.push(line);
+        } else if !paragraph.is_empty() {
+            paragraphs.push(paragraph.join("\n"));
+            paragraph.clear();
+        }
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph.join("\n"));
+    }
+
+    paragraphs
+}
+
+/// `#[validate = json]`: parse the string as JSON
+///
+/// On a syntax error, returns the index of the line with the error, and the error
+#[cfg(feature = "json")]
+pub fn invalid_json(string: &str) -> Option<(usize, String)> {
+    let error = serde_json::from_str::<serde_json::Value>(string).err()?;
+
+    // Errors at the end of the input, like a missing `}`, can be after the last line
+    let line = error.line().max(1) - 1;
+    Some((line, error.to_string()))
+}
+
+/// Without the `json` feature, `#[validate = json]` is always a compile error
+/// so this is never reached
+#[cfg(not(feature = "json"))]
+pub fn invalid_json(_: &str) -> Option<(usize, String)> {
+    unreachable!("`#[validate = json]` without the `json` feature is a compile error")
+}
+
+/// `#[nfc]` and `#[nfd]`: normalize the string to the Unicode normalization form
+#[cfg(feature = "normalize")]
+pub fn normalize(string: &str, normalization: Normalization) -> String {
+    use unicode_normalization::UnicodeNormalization as _;
+
+    match normalization {
+        Normalization::Nfc => string.nfc().collect(),
+        Normalization::Nfd => string.nfd().collect(),
+    }
+}
+
+/// Without the `normalize` feature, `#[nfc]` and `#[nfd]` are always a compile error
+/// so this is never reached
+#[cfg(not(feature = "normalize"))]
+pub fn normalize(_: &str, _: Normalization) -> String {
+    unreachable!("`#[nfc]` and `#[nfd]` without the `normalize` feature are a compile error")
+}
+
+/// 64-bit FNV-1a hash of the string, which is the same on every platform and in every build
+pub fn fnv1a(string: &str) -> u64 {
+    string.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+/// `#[strip_ansi]`: remove ANSI escape sequences like `ESC[31m`
+///
+/// The escape character `ESC` is either the actual character, or written as `\x1b`, `\u{1b}`, `\033` or `\e`.
+/// A sequence is `ESC[`, then any parameters like `1;31`, and ends with a letter like `m`.
+/// Anything else is kept as it is
+pub fn strip_ansi(line: &str) -> String {
+    const ESCAPES: &[&str] = &["\\x1b", "\\x1B", "\\u{1b}", "\\u{1B}", "\\033", "\\e"];
+
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(ch) = rest.chars().next() {
+        let escape_len = if ch == '\u{1b}' {
+            Some(ch.len_utf8())
+        } else {
+            ESCAPES
+                .iter()
+                .find(|escape| rest.starts_with(**escape))
+                .map(|escape| escape.len())
+        };
+
+        // ESC[1;31m
+        //    ^^^^^^ length of everything after ESC, if it is a valid sequence
+        let sequence_len = escape_len.and_then(|escape_len| {
+            let sequence = rest[escape_len..].strip_prefix('[')?;
+            // Parameters like `1;31`, and then intermediate bytes like ` `
+            let parameters = sequence
+                .find(|ch| !matches!(ch, '0'..='?' | ' '..='/'))
+                .unwrap_or(sequence.len());
+            match sequence[parameters..].chars().next() {
+                Some('@'..='~') => Some(escape_len + 1 + parameters + 1),
+                _ => None,
+            }
+        });
+
+        match sequence_len {
+            Some(len) => rest = &rest[len..],
+            None => {
+                stripped.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+
+    stripped
+}
+
+/// `#[check_braces]`: find the braces which don't have a matching brace, in any of the lines
+///
+/// `{{` and `}}` are escaped braces, so they are skipped. Returns the index of the line
+/// with each unmatched brace, and the brace
+pub fn unbalanced_braces<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<(usize, char)> {
+    let mut unbalanced = Vec::new();
+    // Line of each `{` which isn't closed yet
+    let mut open = Vec::new();
+
+    for (index, line) in lines.into_iter().enumerate() {
+        let mut chars = line.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if (ch == '{' || ch == '}') && chars.peek() == Some(&ch) {
+                chars.next();
+            } else if ch == '{' {
+                open.push(index);
+            } else if ch == '}' && open.pop().is_none() {
+                unbalanced.push((index, '}'));
+            }
+        }
+    }
+    unbalanced.extend(open.into_iter().map(|index| (index, '{')));
+    unbalanced.sort_by_key(|(index, _)| *index);
+
+    unbalanced
+}
+
+/// `#[dedent]`: remove the indentation which all lines have in common
+///
+/// Lines which only contain whitespace don't count, and become empty.
+///
+/// The common indentation is the one of the least indented line. If other lines don't start with
+/// exactly the same whitespace, like a tab where there is a space, there is no common indentation:
+/// nothing is removed, and instead the index of the least indented line is returned together with
+/// the indices of every inconsistent line
+pub fn dedent<'a>(lines: impl IntoIterator<Item = &'a mut String>) -> (usize, Vec<usize>) {
+    let mut lines = lines.into_iter().collect::<Vec<_>>();
+
+    // Indentation of the least indented line, and its index
+    let common = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index, indentation(line)))
+        .min_by_key(|(_, indentation)| indentation.len());
+    let Some((least_indented, common)) = common else {
+        // Every line is blank
+        for line in &mut lines {
+            line.clear();
+        }
+        return (0, Vec::new());
+    };
+    let common = common.to_string();
+
+    // Compare the actual whitespace, because `\t ` and ` \t` have the same length
+    let inconsistent = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with(&common))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if !inconsistent.is_empty() {
+        return (least_indented, inconsistent);
+    }
+
+    for line in &mut lines {
+        if line.trim().is_empty() {
+            line.clear();
+        } else {
+            line.drain(..common.len());
+        }
+    }
+
+    (least_indented, Vec::new())
+}
+
+/// `#[dedent_first]`: remove the indentation of the first line which isn't blank from every line
+///
+/// Lines which don't start with that exact indentation, because they are less indented
+/// or use different whitespace, are kept as-is
+pub fn dedent_first<'a>(lines: impl IntoIterator<Item = &'a mut String>) {
+    let mut lines = lines.into_iter().collect::<Vec<_>>();
+
+    let Some(first) = lines.iter().find(|line| !line.trim().is_empty()) else {
+        return;
+    };
+    let prefix = indentation(first).to_string();
+
+    for line in &mut lines {
+        if line.starts_with(&prefix) {
+            line.drain(..prefix.len());
+        }
+    }
+}
+
+/// Leading spaces and tabs of the line
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// `#[wrap = N]`: wrap the line at word boundaries, so each line is at most `width` characters.
+///
+/// - Words longer than `width` are not split, they overflow instead. URLs are never broken
+///   because they don't contain whitespace
+/// - Indentation of the line is kept on the first line
+/// - Lines that already fit are returned as-is, so blank lines stay blank
+/// - `#[hang = M]`: every line after the first is indented by `hang` spaces
+/// - `#[wrap_preserve_indent]`: every line after the first also starts with the indentation
+///   of the line, before the `hang` spaces
+pub fn wrap(line: &str, width: usize, hang: usize, preserve_indent: bool) -> Vec<String> {
+    if line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+
+    let content = line.trim_start();
+    let indent = &line[..line.len() - content.len()];
+
+    let mut lines = Vec::new();
+    let mut current = indent.to_string();
+    let mut current_width = indent.chars().count();
+    // Whether `current` has any words, and not just indentation
+    let mut has_words = false;
+
+    for word in content.split_whitespace() {
+        let word_width = word.chars().count();
+
+        if has_words {
+            if current_width + 1 + word_width <= width {
+                current.push(' ');
+                current_width += 1;
+            } else {
+                lines.push(std::mem::take(&mut current));
+                current_width = hang;
+                if preserve_indent {
+                    current.push_str(indent);
+                    current_width += indent.chars().count();
+                }
+                current.extend(std::iter::repeat(' ').take(hang));
+            }
+        }
+
+        current.push_str(word);
+        current_width += word_width;
+        has_words = true;
+    }
+
+    lines.push(current);
+
+    lines
+}
+
+/// `#[emit_toc]`: a nested Markdown list linking to every heading, like `- [Title](#title)`
+///
+/// Headings are lines starting with 1 to 6 `#` followed by a space. Lines in fenced code blocks are skipped.
+/// The least nested headings aren't indented, and each level deeper is indented by 2 more spaces.
+pub fn table_of_contents<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let text = line.trim_start_matches('#');
+        let level = line.len() - text.len();
+        if !(1..=6).contains(&level) || !text.starts_with(' ') {
+            continue;
+        }
+        // `## Title ##` has an optional closing sequence
+        let text = text.trim();
+        let text = match text.trim_end_matches('#') {
+            rest if rest.is_empty() || rest.ends_with(' ') => rest.trim_end(),
+            _ => text,
+        };
+        if !text.is_empty() {
+            headings.push((level, text));
+        }
+    }
+
+    let Some(min_level) = headings.iter().map(|(level, _)| *level).min() else {
+        return Vec::new();
+    };
+
+    let mut slugs: Vec<String> = Vec::new();
+    headings
+        .into_iter()
+        .map(|(level, text)| {
+            let slug = slugify(text);
+            // Same as GitHub, repeated headings get a `-1`, `-2`... suffix
+            let repeats = slugs.iter().filter(|other| **other == slug).count();
+            let anchor = if repeats == 0 {
+                slug.clone()
+            } else {
+                format!("{slug}-{repeats}")
+            };
+            slugs.push(slug);
+
+            format!("{}- [{text}](#{anchor})", "  ".repeat(level - min_level))
+        })
+        .collect()
+}
+
+/// Anchor of a Markdown heading, the same as GitHub generates: `Hello, World!` becomes `hello-world`
+///
+/// Letters are lowercased, spaces become `-`, and punctuation other than `-` and `_` is removed
+fn slugify(heading: &str) -> String {
+    heading
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            ch if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// CRC-32 (IEEE 802.3), the same one used by zlib and PNG
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+/// `#[emit_raw_bytes_hex_dump]`: canonical hex dump, the same as `hexdump -C` would output.
+///
+/// ```text
+/// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 66 6f  |Hello, world!.fo|
+/// 00000010  6f                                                |o|
+/// 00000011
+/// ```
+///
+/// Unlike `hexdump -C`, repeated lines are not collapsed into `*`
+pub fn hex_dump(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    let mut dump = String::new();
+
+    for (index, line) in bytes.chunks(16).enumerate() {
+        // 00000010  6f                                                |o|
+        // ^^^^^^^^^^
+        write!(dump, "{:08x}  ", index * 16).expect("writing to a `String` can't fail");
+
+        // 00000010  6f                                                |o|
+        //           ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+        for column in 0..16 {
+            match line.get(column) {
+                Some(byte) => {
+                    write!(dump, "{byte:02x} ").expect("writing to a `String` can't fail");
+                }
+                None => dump.push_str("   "),
+            }
+            // extra space between the 2 halves
+            if column == 7 {
+                dump.push(' ');
+            }
+        }
+
+        // 00000010  6f                                                |o|
+        //                                                             ^^^
+        dump.push(' ');
+        dump.push('|');
+        dump.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+        dump.push('|');
+        dump.push('\n');
+    }
+
+    // The last line is the total number of bytes
+    write!(dump, "{:08x}", bytes.len()).expect("writing to a `String` can't fail");
+
+    dump
+}
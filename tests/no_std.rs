@@ -0,0 +1,47 @@
+//! The owned outputs with the `alloc` feature, in a crate without `std`
+#![cfg(feature = "alloc")]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use docstr::{docstr, docstr_boxed, docstr_cow};
+
+#[test]
+fn literal() {
+    assert_eq!(
+        docstr!(
+            /// a
+            /// b
+        ),
+        "a\nb"
+    );
+}
+
+#[test]
+fn boxed() {
+    let boxed: Box<str> = docstr_boxed!(
+        /// a
+        /// b
+    );
+    assert_eq!(&*boxed, "a\nb");
+}
+
+#[test]
+fn cow() {
+    let name = "Bob";
+    assert!(matches!(
+        docstr_cow!(format!
+            /// Hello, {name}!
+        ),
+        Cow::Owned(s) if s == "Hello, Bob!"
+    ));
+    assert!(matches!(
+        docstr_cow!(
+            /// Hello!
+        ),
+        Cow::Borrowed("Hello!")
+    ));
+}
@@ -1,5 +1,8 @@
 #![cfg(test)]
-use docstr::docstr;
+use docstr::{
+    docstr, docstr_boxed, docstr_bytes, docstr_cow, docstr_hashed, docstr_lines,
+    docstr_lines_count, docstr_map, docstr_paragraphs, docstr_spanned, docstr_tee, docstr_with_len,
+};
 
 const AGE: u32 = 19;
 
@@ -9,6 +12,20 @@ fn ui() {
     t.compile_fail("tests/ui/*.rs");
 }
 
+#[test]
+#[cfg(feature = "json")]
+fn ui_json() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/json/*.rs");
+}
+
+#[test]
+#[cfg(feature = "normalize")]
+fn ui_normalize() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/normalize/*.rs");
+}
+
 #[test]
 fn empty() {
     const A: &str = docstr!(
@@ -18,6 +35,60 @@ fn empty() {
     assert_eq!(A, "");
 }
 
+#[test]
+fn empty_lines_are_kept() {
+    assert_eq!(
+        docstr!(
+            ///
+            ///
+            ///
+        ),
+        "\n\n"
+    );
+    assert_eq!(
+        docstr!(
+            #[doc = ""]
+            #[doc = ""]
+            #[doc = ""]
+        ),
+        "\n\n"
+    );
+    assert_eq!(
+        docstr!(
+            ///
+            /// a
+            ///
+            ///
+        ),
+        "\na\n\n"
+    );
+    // No line to take the indentation from
+    assert_eq!(
+        docstr!(#[dedent]
+            ///
+            ///
+            ///
+        ),
+        "\n\n"
+    );
+    assert_eq!(
+        docstr_lines!(
+            ///
+            ///
+            ///
+        ),
+        &["", "", ""]
+    );
+    assert_eq!(
+        docstr_lines_count!(
+            #[doc = ""]
+            #[doc = ""]
+            #[doc = ""]
+        ),
+        3
+    );
+}
+
 #[test]
 fn full_path() {
     assert_eq!(
@@ -35,6 +106,20 @@ fn full_path() {
         ),
         "foo\nbarhello"
     );
+    let name = "world";
+    assert_eq!(
+        docstr!(::std::format!
+            /// hello
+            /// {name}
+        ),
+        "hello\nworld"
+    );
+    assert_eq!(
+        docstr!(::core::concat!
+            /// foo
+        ),
+        "foo"
+    );
 }
 
 /// Works with constants
@@ -69,6 +154,163 @@ fn format() {
     );
 }
 
+/// `=>` ends the doc comments, so the arguments after it can start with `#`
+#[test]
+fn explicit_end_of_doc_comments() {
+    macro_rules! hashed {
+        ($text:literal, # $($rest:tt)*) => {
+            concat!($text, " #", stringify!($($rest)*))
+        };
+    }
+
+    assert_eq!(
+        docstr!(hashed!
+            /// a
+            /// b
+            => #tag
+        ),
+        "a\nb #tag"
+    );
+    // `#[doc]` after `=>` is not a doc comment either
+    assert_eq!(
+        docstr!(hashed!
+            /// a
+            => #[doc = " b"]
+        ),
+        "a #[doc = \" b\"]"
+    );
+    // Without any arguments after it
+    assert_eq!(
+        docstr!(
+            /// a
+            =>
+        ),
+        "a"
+    );
+}
+
+/// A `#` before the doc comments which doesn't start an attribute is an argument
+#[test]
+fn hash_before_doc_comments() {
+    macro_rules! hashed {
+        (# $tag:ident, $text:literal) => {
+            concat!("#", stringify!($tag), " ", $text)
+        };
+    }
+
+    assert_eq!(
+        docstr!(#[trim_trailing_comma] hashed! #tag
+            /// a
+        ),
+        "#tag a"
+    );
+}
+
+#[test]
+fn second_region_of_doc_comments() {
+    const NAME: &str = "Bob";
+    macro_rules! name {
+        () => {
+            "Bob"
+        };
+    }
+
+    // prefix, argument, suffix
+    assert_eq!(
+        docstr!(concat!
+            /// Hello,
+            #[doc = " my name is "]
+            name!()
+            /// .
+            /// Bye!
+        ),
+        "Hello,\nmy name is Bob.\nBye!"
+    );
+    // Commas around the arguments are optional
+    assert_eq!(
+        docstr!(concat!
+            /// <
+            , 1, 2,
+            /// >
+            "!"
+        ),
+        "<12>!"
+    );
+    // Arguments before the 1st region, and modes applied to both regions
+    assert_eq!(
+        docstr!(#[dedent] #[upper] format! "{}{}{}",
+            ///     a
+            ///       b
+            NAME
+            ///     c
+        ),
+        "A\n  BBobC"
+    );
+    // Works with `=>` after the 2nd region
+    macro_rules! hashed {
+        ($a:literal, $b:literal, $c:literal, # $($rest:tt)*) => {
+            concat!($a, $b, $c, " #", stringify!($($rest)*))
+        };
+    }
+    assert_eq!(
+        docstr!(hashed!
+            /// a
+            "b"
+            /// c
+            => #tag
+        ),
+        "abc #tag"
+    );
+    assert_eq!(
+        docstr_spanned!(concat!
+            /// a
+            1
+            /// b
+        ),
+        "a1b"
+    );
+}
+
+#[test]
+fn cfg_lines() {
+    let help: String = docstr!(
+        /// usage:
+        #[cfg(unix)]
+        /// unix
+        #[cfg(not(unix))]
+        /// not unix
+        #[cfg(any())]
+        /// never
+        /// end
+    );
+    if cfg!(unix) {
+        assert_eq!(help, "usage:\nunix\nend");
+    } else {
+        assert_eq!(help, "usage:\nnot unix\nend");
+    }
+
+    // All lines are excluded
+    assert_eq!(
+        docstr!(
+            #[cfg(any())]
+            /// never
+        ),
+        ""
+    );
+    // Several cfgs before the same line must all hold
+    assert_eq!(
+        docstr!(#[upper, join = " "]
+            /// a
+            #[cfg(all())]
+            #[cfg(any())]
+            /// b
+            #[cfg(all())]
+            /// c
+        ),
+        "A C"
+    );
+}
+
 /// NO macro, but `{}`
 #[test]
 fn fake_interpolation() {
@@ -139,3 +381,2146 @@ fn escape() {
         "hello \"world\" ' \\ ! ()\n///\\\\/\\// \\u{0032}"
     );
 }
+
+#[test]
+#[cfg(feature = "compress")]
+fn deflate() {
+    use std::io::Read as _;
+
+    docstr!(#[deflate = TEMPLATE]
+        /// <html>
+        ///     <body>Hello, world!</body>
+        /// </html>
+    );
+
+    let mut template = String::new();
+    flate2::read::DeflateDecoder::new(TEMPLATE)
+        .read_to_string(&mut template)
+        .unwrap();
+
+    assert_eq!(template, "<html>\n    <body>Hello, world!</body>\n</html>");
+    assert_eq!(TEMPLATE_LEN, template.len());
+
+    mod public {
+        docstr::docstr!(#[deflate = pub(crate) LARGE]
+            /// aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+            /// aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+        );
+    }
+
+    assert!(
+        public::LARGE.len() < public::LARGE_LEN,
+        "repetitive input is smaller"
+    );
+}
+
+#[test]
+#[cfg(feature = "normalize")]
+fn normalize() {
+    // `e` followed by U+0301 COMBINING ACUTE ACCENT, and `é`
+    const COMPOSED: &str = docstr!(#[nfc]
+        /// café
+        /// é
+    );
+    assert_eq!(COMPOSED, "caf\u{e9}\n\u{e9}");
+
+    assert_eq!(
+        docstr!(#[nfd]
+            /// café
+        ),
+        "cafe\u{301}"
+    );
+    // After `#[upper]`
+    assert_eq!(
+        docstr!(#[nfc] #[upper]
+            /// é
+        ),
+        "\u{c9}"
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn validate_json() {
+    const CONFIG: &str = docstr!(#[validate = json]
+        /// {
+        ///     "name": "docstr",
+        ///     "nested": { "list": [1, 2.5, null, true] }
+        /// }
+    );
+    assert!(CONFIG.ends_with('}'));
+
+    // Validated after the other modes
+    assert_eq!(
+        docstr!(#[validate = json] #[strip_comments = "//"]
+            /// // a comment is not valid JSON
+            /// "string"
+        ),
+        "\"string\""
+    );
+}
+
+#[test]
+fn with_len() {
+    let (s, n) = docstr_with_len!(
+        /// abc
+    );
+    assert_eq!(s, "abc");
+    assert_eq!(n, 3);
+
+    const MULTIBYTE: (&str, usize) = docstr_with_len!(
+        /// ünï
+        /// códe
+    );
+    let buffer = [0_u8; MULTIBYTE.1];
+    assert_eq!(buffer.len(), MULTIBYTE.0.len());
+}
+
+#[test]
+fn hashed() {
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hash = 0xcbf29ce484222325_u64;
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    const HASHED: (&str, u64) = docstr_hashed!(
+        /// ünï
+        /// códe
+    );
+    assert_eq!(HASHED.0, "ünï\ncóde");
+    assert_eq!(HASHED.1, fnv1a(HASHED.0.as_bytes()));
+
+    // Reference values of FNV-1a
+    assert_eq!(
+        docstr_hashed!(
+            /// a
+        ),
+        ("a", 0xaf63dc4c8601ec8c)
+    );
+    assert_eq!(
+        docstr_hashed!(
+            /// foobar
+        ),
+        ("foobar", 0x85944171f73967e8)
+    );
+}
+
+#[test]
+fn several_modes_in_one_attribute() {
+    let name = "world";
+    assert_eq!(
+        docstr!(#[dedent, trim_blank_lines, join = "\r\n"], format!
+            ///
+            ///    hello
+            ///      {name}
+            ///
+        ),
+        "hello\r\n  world"
+    );
+    assert_eq!(
+        docstr!(#[upper,] #[number, number_format = "{n}, "]
+            /// a
+            /// b
+        ),
+        "1, A\n2, B"
+    );
+    // Grouped modes and short flags together
+    assert_eq!(
+        docstr!([t] #[wrap = 3, hang = 2]
+            ///
+            /// aaa bbb
+        ),
+        "aaa\n  bbb"
+    );
+}
+
+#[test]
+fn short_flags() {
+    assert_eq!(
+        docstr!([d, t],
+            ///
+            ///     a
+            ///       b
+            ///
+        ),
+        "a\n  b"
+    );
+    // Together with other modes and a macro
+    let name = "world";
+    assert_eq!(
+        docstr!([d] #[join = " "] format!
+            ///   hello
+            ///   {name}
+        ),
+        "hello world"
+    );
+    assert_eq!(
+        docstr!([n, r]
+            /// "a"
+        ),
+        r#"1: "a""#
+    );
+}
+
+#[test]
+fn lines_slice() {
+    let lines: &'static [&'static str] = docstr_lines!(
+        /// a
+        ///   b
+        ///
+    );
+    assert_eq!(lines, ["a", "  b", ""]);
+
+    // Dedent is computed across all lines, like for the joined string
+    const DEDENTED: &[&str] = docstr_lines!(#[dedent]
+        ///     fn main() {
+        ///         println!("hi");
+        ///
+        ///     }
+    );
+    assert_eq!(DEDENTED, ["fn main() {", "    println!(\"hi\");", "", "}"]);
+    assert_eq!(
+        DEDENTED.join("\n"),
+        docstr!([d]
+            ///     fn main() {
+            ///         println!("hi");
+            ///
+            ///     }
+        )
+    );
+    assert_eq!(
+        docstr_lines!([d, t]
+            ///
+            ///   x
+            ///     y
+            ///
+        ),
+        &["x", "  y"]
+    );
+}
+
+#[test]
+fn paragraphs() {
+    let paragraphs: [&str; 3] = docstr_paragraphs!(
+        /// first
+        ///
+        /// second, line 1
+        /// second, line 2
+        ///
+        /// third
+    );
+    assert_eq!(
+        paragraphs,
+        ["first", "second, line 1\nsecond, line 2", "third"]
+    );
+
+    // Leading, trailing and repeated blank lines don't produce empty paragraphs
+    const PARAGRAPHS: [&str; 2] = docstr_paragraphs!(
+        ///
+        ///
+        /// a
+        ///
+        ///
+        ///
+        ///   b
+        ///
+    );
+    assert_eq!(PARAGRAPHS, ["a", "  b"]);
+
+    let empty: [&str; 0] = docstr_paragraphs!(#[allow_empty]);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn lines_count() {
+    assert_eq!(
+        docstr_lines_count!(
+            /// a
+            /// b
+        ),
+        2
+    );
+    assert_eq!(
+        docstr_lines_count!(
+            /// a
+            /// b
+            ///
+        ),
+        3
+    );
+
+    const LINES: usize = docstr_lines_count!(
+        ///
+        /// {
+        ///
+        ///     }
+    );
+    let string = docstr!(
+        ///
+        /// {
+        ///
+        ///     }
+    );
+    assert_eq!(LINES, string.split('\n').count());
+}
+
+#[test]
+fn emit_match_arms() {
+    fn value(key: &str) -> u32 {
+        docstr!(#[emit_match_arms] key
+            /// "a" => 1
+            /// "b" | "c" => 2,
+            ///
+            /// other if other.len() > 1 => other.len() as u32
+            /// _ => 0
+        )
+    }
+
+    assert_eq!(value("a"), 1);
+    assert_eq!(value("c"), 2);
+    assert_eq!(value("long"), 4);
+    assert_eq!(value("z"), 0);
+}
+
+#[test]
+fn emit_enum_variants() {
+    docstr!(#[emit_enum_variants] enum Plain
+        /// A
+        ///
+        ///   B
+        /// Ünicode
+    );
+    let all = [Plain::A, Plain::B, Plain::Ünicode];
+    assert_eq!(all.len(), 3);
+
+    docstr!(#[emit_enum_variants(derive(Debug, Clone, Copy, PartialEq), allow(dead_code),)]
+        pub(crate) enum Level
+        /// Low
+        /// High
+    );
+    assert_eq!(format!("{:?}", Level::High), "High");
+    assert_eq!(Level::Low, Level::Low.clone());
+}
+
+#[test]
+fn emit_byte_match() {
+    fn decode(byte: u8) -> Option<&'static str> {
+        docstr!(#[emit_byte_match] byte
+            /// 0x01 => Some("SOH")
+            /// 0x02 | 0x03 => Some("STX or ETX")
+            /// b'\n' => Some("LF"),
+            /// 200_u8..=255 => Some("high")
+            /// _ => None
+        )
+    }
+
+    assert_eq!(decode(0x01), Some("SOH"));
+    assert_eq!(decode(3), Some("STX or ETX"));
+    assert_eq!(decode(10), Some("LF"));
+    assert_eq!(decode(0xFF), Some("high"));
+    assert_eq!(decode(0x7F), None);
+}
+
+#[test]
+fn strip() {
+    assert_eq!(
+        docstr!(#[strip = 4]
+            ///     four
+            ///  one
+            ///      five
+            ///
+            ///	x
+        ),
+        " four\none\n  five\n\n\tx"
+    );
+    assert_eq!(
+        docstr!(#[strip = 0]
+            /// a
+            ///b
+        ),
+        " a\nb"
+    );
+}
+
+#[test]
+fn space_strip() {
+    assert_eq!(
+        docstr!(#[space_strip = all]
+            ///  two
+            ///      five
+            ///	tab
+        ),
+        "two\nfive\n\ttab"
+    );
+    assert_eq!(
+        docstr!(#[space_strip = one]
+            ///  two
+        ),
+        " two"
+    );
+    assert_eq!(
+        docstr!(#[space_strip = none]
+            ///  two
+        ),
+        "  two"
+    );
+}
+
+#[test]
+fn trim_trailing_comma() {
+    macro_rules! no_trailing_comma {
+        ($($arg:expr),*) => {
+            [$($arg),*].concat()
+        };
+    }
+
+    // Without the mode, the trailing comma is passed as-is
+    assert_eq!(
+        docstr!(format!
+            /// {}
+            "arg",
+        ),
+        "arg"
+    );
+
+    assert_eq!(
+        docstr!(#[trim_trailing_comma] no_trailing_comma!
+            /// x
+            "arg",
+        ),
+        "xarg"
+    );
+    assert_eq!(
+        docstr!(#[trim_trailing_comma] no_trailing_comma!
+            /// x
+            "a", "b"
+        ),
+        "xab"
+    );
+    assert_eq!(
+        docstr!(#[trim_trailing_comma] no_trailing_comma!
+            /// only
+        ),
+        "only"
+    );
+}
+
+#[test]
+fn dedent() {
+    assert_eq!(
+        docstr!(#[dedent]
+            ///     a
+            ///
+            ///       b
+            #[doc = "     "]
+            ///     c
+        ),
+        "a\n\n  b\n\nc"
+    );
+    assert_eq!(
+        docstr!(#[dedent]
+            #[doc = "\t\tx"]
+            #[doc = "\ty"]
+            #[doc = "\t"]
+        ),
+        "\tx\ny\n"
+    );
+    assert_eq!(
+        docstr!(#[dedent]
+            /// not indented
+            ///     indented
+        ),
+        "not indented\n    indented"
+    );
+    assert_eq!(
+        docstr!(#[dedent]
+            #[doc = "   "]
+        ),
+        ""
+    );
+}
+
+#[test]
+fn tabs() {
+    assert_eq!(
+        docstr!(#[tabs = 4]
+            #[doc = "\tfoo"]
+            #[doc = "ab\tc\t\td"]
+            /// no tabs
+        ),
+        "    foo\nab  c       d\nno tabs"
+    );
+}
+
+#[test]
+fn wrap_in() {
+    assert_eq!(
+        docstr!(#[wrap_in("<!-- ", " -->")]
+            /// TODO
+        ),
+        "<!-- TODO -->"
+    );
+    assert_eq!(
+        docstr!(#[wrap_in("\"\"\"\n", "\n\"\"\"")]
+            /// Docstring of a Python function
+            ///
+            /// Spans multiple lines
+        ),
+        "\"\"\"\nDocstring of a Python function\n\nSpans multiple lines\n\"\"\""
+    );
+    assert_eq!(
+        docstr!(#[wrap_in("/* ", " */")] format!
+            /// {} + {}
+            1, 2
+        ),
+        "/* 1 + 2 */"
+    );
+}
+
+#[test]
+fn normalize_newlines() {
+    assert_eq!(
+        docstr!(#[normalize_newlines]
+            #[doc = "foo\r"]
+            #[doc = "a\rb\r\r"]
+        ),
+        "foo\na\rb"
+    );
+    assert_eq!(
+        docstr!(#[normalize_newlines = all]
+            #[doc = "foo\r"]
+            #[doc = "a\r\nb\r"]
+        ),
+        "foo\na\nb"
+    );
+    assert_eq!(
+        docstr!(
+            #[doc = "foo\r"]
+        ),
+        "foo\r",
+        "opt-in"
+    );
+}
+
+#[test]
+fn explicit_doc_attributes() {
+    assert_eq!(
+        docstr!(
+            #[doc = "foo"]
+            #[doc = " bar"]
+            #[doc = "  baz"]
+        ),
+        "foo\nbar\n baz"
+    );
+    assert_eq!(
+        docstr!(
+            #[doc = r"C:\path\to\file"]
+            #[doc = r#" "quoted" "#]
+            #[doc = r##"has "# inside"##]
+            /// sugar
+        ),
+        "C:\\path\\to\\file\n\"quoted\" \nhas \"# inside\nsugar"
+    );
+    assert_eq!(
+        docstr!(format!
+            #[doc = r"{}\n"]
+            1
+        ),
+        "1\\n"
+    );
+}
+
+#[test]
+fn emit_const_str_slice_of_bytes() {
+    docstr!(#[emit_const_str_slice_of_bytes = TABLE]
+        /// Hé
+        /// "\
+    );
+    assert_eq!(TABLE, "Hé\n\"\\".as_bytes());
+    assert_eq!(TABLE, &[0x48, 0xC3, 0xA9, 0x0A, 0x22, 0x5C]);
+
+    docstr!(#[emit_const_str_slice_of_bytes = pub(crate) EMPTY]
+        ///
+    );
+    assert!(EMPTY.is_empty());
+}
+
+#[test]
+fn to_const_option() {
+    docstr!(#[to_const_option = SOME]
+        /// a
+        ///
+    );
+    assert_eq!(SOME, Some("a\n"));
+
+    docstr!(#[to_const_option = pub(crate) NONE]
+        ///
+    );
+    assert_eq!(NONE, None);
+
+    // empty after the modes have been applied
+    docstr!(#[to_const_option = EMPTY_AFTER_TRIM] #[normalize_newlines]
+        #[doc = "\r"]
+    );
+    assert_eq!(EMPTY_AFTER_TRIM, None);
+}
+
+#[test]
+fn subst() {
+    const SUBSTITUTED: &str = docstr!(#[subst(A = "1", LONG_NAME = "{B}", c = "")]
+        /// {A}{A} {LONG_NAME} [{c}]
+        /// { "json": {A} } {{A}} {A
+    );
+    assert_eq!(SUBSTITUTED, "11 {B} []\n{ \"json\": 1 } {{A}} {A");
+
+    let width = 3;
+    assert_eq!(
+        docstr!(#[subst(UNIT = "ms")] format!
+            /// {:>width$}{UNIT} {x}{UNIT}
+            1,
+            x = 2
+        ),
+        "  1ms 2ms"
+    );
+}
+
+#[test]
+fn join() {
+    assert_eq!(
+        docstr!(#[join = ""]
+            /// SELECT
+            /// x
+            /// FROM y
+        ),
+        "SELECTxFROM y"
+    );
+    assert_eq!(
+        docstr!(#[join = ", "] format!
+            /// {}
+            /// b
+            ///
+            "a"
+        ),
+        "a, b, "
+    );
+    assert_eq!(
+        docstr!(#[join = r"\"]
+            /// only line
+        ),
+        "only line"
+    );
+}
+
+#[test]
+fn emit_const_array_2d() {
+    docstr!(#[emit_const_array_2d = GRID]
+        /// a|b|c
+        ///
+        /// | d |
+        /// ||f|g|h
+    );
+    assert_eq!(GRID.len(), 3);
+    assert!(GRID.iter().all(|row| row.len() == 4));
+    assert_eq!(GRID[0], ["a", "b", "c", ""]);
+    assert_eq!(GRID[1], ["d", "", "", ""]);
+    assert_eq!(GRID[2][1], "f");
+    assert_eq!(GRID[2][3], "h");
+
+    docstr!(#[emit_const_array_2d = pub(crate) EMPTY]
+        ///
+    );
+    assert!(EMPTY.is_empty());
+}
+
+#[test]
+fn diff() {
+    assert_eq!(
+        docstr!(#[as_diff = added]
+            /// a
+            ///
+        ),
+        "+a\n+"
+    );
+    assert_eq!(
+        docstr!(#[as_diff = removed]
+            /// -a
+        ),
+        "--a"
+    );
+    assert_eq!(
+        docstr!(#[diff_markers]
+            /// context
+            ///+added
+            ///-removed
+            ///
+        ),
+        " context\n+added\n-removed\n "
+    );
+    assert_eq!(
+        docstr!(#[diff_markers = "+"]
+            /// new
+            ///-old
+        ),
+        "+new\n-old"
+    );
+}
+
+#[test]
+fn strip_final_newline() {
+    use std::fmt::Write as _;
+
+    let mut diagnostics = String::new();
+    docstr!(#[strip_final_newline] writeln! diagnostics,
+        /// error: {}
+        ///   at line {}
+        ///
+        "unexpected token", 3
+    )
+    .unwrap();
+    assert_eq!(diagnostics, "error: unexpected token\n  at line 3\n");
+
+    docstr!(#[strip_final_newline] eprintln!
+        /// warning: {}
+        ///
+        "no newline at the end"
+    );
+
+    assert_eq!(
+        docstr!(#[strip_final_newline]
+            /// a
+            ///
+            ///
+        ),
+        "a\n"
+    );
+    assert_eq!(
+        docstr!(#[strip_final_newline]
+            /// a
+        ),
+        "a"
+    );
+}
+
+#[test]
+fn repeat() {
+    assert_eq!(
+        docstr!(#[repeat = 3]
+            /// a
+            /// b
+        ),
+        "a\nb\na\nb\na\nb"
+    );
+    assert_eq!(
+        docstr!(#[repeat = 1]
+            /// a
+        ),
+        "a"
+    );
+    assert_eq!(
+        docstr!(#[repeat = 2] #[join = ", "]
+            /// a
+            /// b
+        ),
+        "a, b, a, b"
+    );
+    assert_eq!(
+        docstr!(#[repeat = 2] #[wrap_in("[", "]")]
+            /// a
+            ///
+        ),
+        "[a\n\na\n]"
+    );
+}
+
+#[test]
+fn upper_lower() {
+    assert_eq!(
+        docstr!(#[upper]
+            /// select *
+            /// from straße where é = 'ß'
+        ),
+        "SELECT *\nFROM STRASSE WHERE É = 'SS'"
+    );
+    assert_eq!(
+        docstr!(#[lower]
+            /// MAX_SIZE
+            /// ΣΟΦΊΑ ÀÉÎ
+        ),
+        "max_size\nσοφία àéî"
+    );
+    const KEYWORD: &str = docstr!(#[upper] #[repeat = 2]
+        /// ok
+    );
+    assert_eq!(KEYWORD, "OK\nOK");
+}
+
+#[test]
+fn leading_newline() {
+    assert_eq!(
+        docstr!(#[leading_newline]
+            /// a
+            /// b
+        ),
+        "\na\nb"
+    );
+    assert_eq!(
+        docstr!(#[leading_newline]
+            ///
+            /// a
+        ),
+        "\na"
+    );
+    assert_eq!(
+        docstr!(#[leading_newline]
+            ///
+            ///
+            /// a
+        ),
+        "\n\na",
+        "only the 1st blank line counts"
+    );
+    assert_eq!(
+        docstr!(#[leading_newline]
+            ///
+        ),
+        "\n"
+    );
+}
+
+#[test]
+fn measure_const() {
+    docstr!(#[measure_const = RAGGED]
+        /// a
+        /// longest line
+        ///
+        /// mid line
+    );
+    assert_eq!(RAGGED, (12, 4));
+
+    docstr!(#[measure_const = pub(crate) WIDE] #[tabs = 4]
+        #[doc = "\tüñï"]
+    );
+    assert_eq!(WIDE, (7, 1));
+
+    docstr!(#[measure_const = EMPTY]
+        ///
+    );
+    assert_eq!(EMPTY, (0, 1));
+}
+
+#[test]
+fn emit_static_str_map_sorted() {
+    docstr!(#[emit_static_str_map_sorted = MAP] #[strict_keys]
+        /// zeta: last
+        /// url: https://example.com
+        ///
+        /// alpha:first
+        /// empty:
+    );
+    assert_eq!(
+        MAP,
+        [
+            ("alpha", "first"),
+            ("empty", ""),
+            ("url", "https://example.com"),
+            ("zeta", "last")
+        ]
+    );
+    assert!(MAP.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(MAP.binary_search_by_key(&"url", |(key, _)| key), Ok(2));
+
+    docstr!(#[emit_static_str_map_sorted = pub(crate) DUPLICATES]
+        /// b: 1
+        /// a: 2
+        /// b: 3
+    );
+    assert_eq!(DUPLICATES, [("a", "2"), ("b", "1"), ("b", "3")]);
+
+    docstr!(#[emit_static_str_map_sorted = ARROWS] #[delimiter = "=>"]
+        /// b => 2
+        /// a => 1
+    );
+    assert_eq!(ARROWS, [("a", "1"), ("b", "2")]);
+}
+
+#[test]
+fn map() {
+    const COLORS: &[(&str, &str)] = docstr_map!(
+        /// red = #ff0000
+        /// green=#00ff00
+        ///
+        /// blue = #0000ff
+    );
+    assert_eq!(
+        COLORS,
+        [
+            ("red", "#ff0000"),
+            ("green", "#00ff00"),
+            ("blue", "#0000ff")
+        ]
+    );
+
+    // Only the 1st delimiter splits
+    assert_eq!(
+        docstr_map!(
+            /// url = https://example.com/?a=b
+        ),
+        &[("url", "https://example.com/?a=b")]
+    );
+    assert_eq!(
+        docstr_map!(#[delimiter = ":"]
+            /// name: docstr
+            /// kind: proc-macro
+        ),
+        &[("name", "docstr"), ("kind", "proc-macro")]
+    );
+}
+
+#[test]
+fn unescape_bytes() {
+    assert_eq!(
+        docstr_bytes!(#[unescape]
+            /// \xff\x00
+        ),
+        b"\xff\x00"
+    );
+    assert_eq!(
+        docstr_bytes!(#[unescape]
+            /// \x48i\t\"\\\0
+            /// \xFf\r\n
+        ),
+        b"\x48i\t\"\\\0\n\xff\r\n"
+    );
+    // Characters which aren't escapes become their UTF-8 bytes
+    assert_eq!(
+        docstr_bytes!(#[unescape]
+            /// é\x00
+        ),
+        b"\xc3\xa9\x00"
+    );
+}
+
+#[test]
+fn join_expr() {
+    let separator = String::from(", ");
+    let joined: String = docstr!(#[join_expr = &separator]
+        /// a
+        /// b
+        /// c
+    );
+    assert_eq!(joined, "a, b, c");
+
+    // Other modes are applied to the lines first
+    let separator = " | ";
+    assert_eq!(
+        docstr!(#[join_expr = separator] #[dedent] #[upper]
+            ///   a
+            ///     b
+        ),
+        "A |   B"
+    );
+    assert_eq!(
+        docstr!(#[join_expr = "-"]
+            /// single
+        ),
+        "single"
+    );
+}
+
+#[test]
+fn write_all() -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    let mut socket = Vec::new();
+    docstr_bytes!(#[write_all = socket]
+        /// GET / HTTP/1.1
+        /// Host: example.com
+        ///
+    )?;
+    docstr!(#[write_all = &mut socket]
+        /// body
+    )?;
+    assert_eq!(socket, b"GET / HTTP/1.1\nHost: example.com\nbody");
+
+    // The error is passed through
+    let mut full = [0_u8; 2];
+    let result = docstr_bytes!(#[write_all = &mut full[..]]
+        /// abc
+    );
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::WriteZero);
+    assert_eq!(&full, b"ab");
+
+    Ok(())
+}
+
+#[test]
+fn emit_const_u8_slices_per_line() {
+    docstr!(#[emit_const_u8_slices_per_line = LINES]
+        /// é\x00
+        ///
+        /// "
+        ///
+    );
+    assert_eq!(LINES.len(), 4);
+    assert_eq!(LINES[0], "é\\x00".as_bytes());
+    assert!(LINES[1].is_empty());
+    assert_eq!(LINES[2], b"\"");
+    assert!(LINES[3].is_empty());
+
+    docstr!(#[emit_const_u8_slices_per_line = pub(crate) ONE]
+        ///
+    );
+    assert_eq!(ONE, [b""]);
+}
+
+#[test]
+fn static_ref() {
+    mod generated {
+        docstr::docstr!(#[static_ref = pub(super) HEADER]
+            /// // generated
+            ///
+        );
+    }
+
+    fn header() -> &'static str {
+        generated::HEADER
+    }
+
+    assert_eq!(generated::HEADER, "// generated\n");
+    assert_eq!(header().as_ptr(), generated::HEADER.as_ptr());
+    assert!(std::ptr::eq(&generated::HEADER, &generated::HEADER));
+}
+
+#[test]
+fn named() {
+    mod generated {
+        docstr::docstr!(#[named = pub(super) FIRST]
+            /// a
+        );
+        docstr::docstr!(#[named = pub(super) SECOND]
+            /// b
+        );
+
+        // The guard is named after the 64-bit FNV-1a hash of the string
+        pub(super) const GUARD: () = __DOCSTR_NAMED_AF63DC4C8601EC8C;
+    }
+
+    assert_eq!(generated::FIRST, "a");
+    assert_eq!(generated::SECOND, "b");
+    generated::GUARD
+}
+
+#[test]
+fn regular_comments_between_doc_comments() {
+    assert_eq!(
+        docstr!(
+            /// a
+            // note
+            /// b
+        ),
+        "a\nb"
+    );
+    assert_eq!(
+        docstr!(format!
+            // before
+            /// {} /* not a comment inside the doc comment */
+            /* block */
+            // several
+            // lines
+            ///
+            /// c
+            // after
+            1
+        ),
+        "1 /* not a comment inside the doc comment */\n\nc"
+    );
+}
+
+#[test]
+fn at_placeholder() {
+    macro_rules! middle {
+        ($a:expr, $string:literal, $b:expr) => {
+            format!("{} {} {}", $a, $string, $b)
+        };
+    }
+
+    assert_eq!(
+        docstr!(#[at_placeholder] middle! 1, @, 2
+            /// a
+            /// b
+        ),
+        "1 a\nb 2"
+    );
+    assert_eq!(
+        docstr!(#[at_placeholder] middle! 1
+            /// a
+            @, 2
+        ),
+        "1 a 2"
+    );
+    assert_eq!(
+        docstr!(#[at_placeholder] format!
+            @, 1
+            /// {}
+        ),
+        "1"
+    );
+
+    use std::fmt::Write as _;
+    let mut s = String::new();
+    docstr!(#[at_placeholder] #[into = s] write!
+        /// {}
+        @, "into goes first"
+    )
+    .unwrap();
+    assert_eq!(s, "into goes first");
+}
+
+#[test]
+fn allow_empty() {
+    assert_eq!(docstr!(#[allow_empty]), "");
+    assert_eq!(
+        docstr!(#[allow_empty]
+            /// a
+        ),
+        "a"
+    );
+    assert_eq!(docstr!(#[allow_empty] #[wrap_in("<", ">")]), "<>");
+
+    macro_rules! lines {
+        ($($line:literal)*) => {
+            docstr!(#[allow_empty] #[join = ", "] $(#[doc = $line])*)
+        };
+    }
+    assert_eq!(lines!(), "");
+    assert_eq!(lines!(" a" " b"), "a, b");
+}
+
+#[test]
+fn four_slashes_are_not_doc_comments() {
+    assert_eq!(
+        docstr!(
+            /// a
+            //// b
+            ///// c
+            /// d
+        ),
+        "a\nd"
+    );
+    assert_eq!(
+        docstr!(
+            /// a
+            /// /b
+            #[doc = "// c"]
+        ),
+        "a\n/b\n// c"
+    );
+}
+
+#[test]
+fn assert_composition() {
+    let actual = String::from("a\nb");
+    docstr!(assert_eq! actual,
+        /// a
+        /// b
+    );
+    docstr!(assert_eq! actual
+        /// a
+        /// b
+    );
+    docstr!(assert_ne! actual,
+        /// a
+    );
+    docstr!(assert_eq! actual,
+        /// a
+        /// b
+        "custom message: {}", 1
+    );
+    assert!(docstr!(matches! "x",
+        /// x
+    ));
+}
+
+#[test]
+fn logging_macro_target() {
+    // Has the same syntax as the macros of the `log` crate
+    macro_rules! info {
+        (target: $target:expr, $($arg:tt)+) => {
+            format!("[{}] {}", $target, format_args!($($arg)+))
+        };
+        ($($arg:tt)+) => {
+            format!("[default] {}", format_args!($($arg)+))
+        };
+    }
+
+    assert_eq!(
+        docstr!(info! target: "net",
+            /// connection {} open
+            42
+        ),
+        "[net] connection 42 open"
+    );
+    assert_eq!(
+        docstr!(info! target: "net"
+            /// connection {} open
+            42
+        ),
+        "[net] connection 42 open"
+    );
+    assert_eq!(
+        docstr!(info!
+            /// connection {} open
+            42
+        ),
+        "[default] connection 42 open"
+    );
+}
+
+#[test]
+fn adjacent_literals() {
+    assert_eq!(
+        docstr!(
+            #[doc = " foo" "bar"]
+            #[doc = "a" r"\b" r#" "c""#]
+            #[doc = "" " x"]
+        ),
+        "foobar\na\\b \"c\"\nx"
+    );
+}
+
+#[test]
+fn escape_for_makefile() {
+    assert_eq!(
+        docstr!(#[escape_for_makefile]
+            /// CC := gcc
+            /// all:
+            ///	$(CC) -o $@ $$VAR $VAR
+        ),
+        "CC := gcc\nall:\n\t$$(CC) -o $$@ $$$$VAR $$VAR"
+    );
+    assert_eq!(
+        docstr!(#[escape_for_makefile] format!
+            /// {}
+            "$x"
+        ),
+        "$x",
+        "arguments to the macro are not escaped"
+    );
+}
+
+#[test]
+fn line_hash_comments() {
+    assert_eq!(
+        docstr!(#[line_hash_comments]
+            /// hello
+            ///
+            /// The quick brown fox jumps over the lazy dog
+        ),
+        concat!(
+            "hello # crc:3610a686\n",
+            " # crc:00000000\n",
+            "The quick brown fox jumps over the lazy dog # crc:414fa339"
+        )
+    );
+}
+
+#[test]
+fn format_args() {
+    use std::fmt::{self, Write as _};
+
+    struct Greeting<'a>(&'a str);
+
+    impl fmt::Display for Greeting<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_fmt(docstr!(format_args!
+                /// Hello,
+                /// {}!
+                self.0
+            ))
+        }
+    }
+
+    assert_eq!(Greeting("world").to_string(), "Hello,\nworld!");
+
+    let name = "Bob";
+    let mut s = String::new();
+    write!(
+        s,
+        "[{}]",
+        docstr!(format_args!
+            /// {name} is {} years old
+            21
+        )
+    )
+    .unwrap();
+    assert_eq!(s, "[Bob is 21 years old]");
+}
+
+#[test]
+fn question_mark() {
+    use std::fmt;
+
+    fn render(w: &mut impl fmt::Write, items: &[&str]) -> fmt::Result {
+        docstr!(writeln! w,
+            /// items:
+        )?;
+        for item in items {
+            docstr!(writeln! w
+                /// - {item}
+            )?;
+        }
+        docstr!(write! w,
+            /// end
+        )?;
+        Ok(())
+    }
+
+    let mut s = String::new();
+    render(&mut s, &["a", "b"]).unwrap();
+    assert_eq!(s, "items:\n- a\n- b\nend");
+
+    // The error is propagated
+    struct Full;
+    impl fmt::Write for Full {
+        fn write_str(&mut self, _: &str) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+    assert_eq!(render(&mut Full, &["a"]), Err(fmt::Error));
+}
+
+#[test]
+fn ensure_shebang() {
+    assert_eq!(
+        docstr!(#[ensure_shebang = "/bin/sh"]
+            /// set -e
+            /// make
+        ),
+        "#!/bin/sh\nset -e\nmake"
+    );
+    assert_eq!(
+        docstr!(#[ensure_shebang = "/bin/sh"]
+            /// #!/usr/bin/env python3
+            /// print("hi")
+        ),
+        "#!/usr/bin/env python3\nprint(\"hi\")",
+        "existing shebang is kept"
+    );
+}
+
+#[test]
+fn validate_no_trailing_whitespace() {
+    assert_eq!(
+        docstr!(#[validate_no_trailing_whitespace]
+            /// clean
+            ///
+            ///     indented
+        ),
+        "clean\n\n    indented"
+    );
+}
+
+#[test]
+fn into() {
+    use std::fmt::Write as _;
+
+    let mut s = String::new();
+    docstr!(#[into = s] write!
+        /// hello
+    )
+    .unwrap();
+    assert_eq!(s, "hello");
+
+    let mut buffers = [String::new(), String::new()];
+    docstr!(#[into = buffers[1]] writeln!
+        /// {} and {}
+        "a", "b"
+    )
+    .unwrap();
+    assert_eq!(buffers[1], "a and b\n");
+
+    // composes with the tokens before the doc comments
+    macro_rules! join3 {
+        ($a:expr, $b:expr, $c:expr $(,)?) => {
+            format!("{}|{}|{}", $a, $b, $c)
+        };
+    }
+    assert_eq!(
+        docstr!(#[into = "first"] join3! "second"
+            /// third
+        ),
+        "first|second|third"
+    );
+}
+
+#[test]
+fn chunk_lines() {
+    const PAGES: &[&str] = docstr!(#[chunk_lines(3)]
+        /// a
+        /// b
+        /// c
+        /// d
+        ///
+        /// f
+        /// g
+    );
+    assert_eq!(PAGES, ["a\nb\nc", "d\n\nf", "g"]);
+
+    let single: &[&str] = docstr!(#[chunk_lines = 10]
+        /// a
+        /// b
+    );
+    assert_eq!(single, ["a\nb"]);
+}
+
+#[test]
+fn emit_raw_bytes_hex_dump() {
+    assert_eq!(
+        docstr!(#[emit_raw_bytes_hex_dump]
+            /// 0123456789abcdef
+            /// é\t~
+        ),
+        concat!(
+            "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n",
+            "00000010  0a c3 a9 5c 74 7e                                 |...\\t~|\n",
+            "00000016"
+        )
+    );
+    assert_eq!(
+        docstr!(#[emit_raw_bytes_hex_dump]
+            ///
+        ),
+        "00000000"
+    );
+}
+
+#[test]
+fn wrap() {
+    assert_eq!(
+        docstr!(#[wrap = 20]
+            /// The quick brown fox jumps over the lazy dog
+        ),
+        "The quick brown fox\njumps over the lazy\ndog"
+    );
+    assert_eq!(
+        docstr!(#[wrap = 10]
+            /// short
+            ///
+            ///   indented words wrap
+            /// https://example.com/long
+        ),
+        "short\n\n  indented\nwords wrap\nhttps://example.com/long"
+    );
+}
+
+#[test]
+fn wrap_preserve_indent() {
+    assert_eq!(
+        docstr!(#[wrap = 16] #[wrap_preserve_indent]
+            ///     one two three four five
+            /// no indent at all here
+        ),
+        "    one two\n    three four\n    five\nno indent at all\nhere"
+    );
+    assert_eq!(
+        docstr!(#[wrap = 14] #[wrap_preserve_indent] #[hang = 2]
+            #[doc = "\t- one two three"]
+        ),
+        "\t- one two\n\t  three"
+    );
+}
+
+#[test]
+fn emit_toc() {
+    assert_eq!(
+        docstr!(#[emit_toc]
+            /// Intro, not a heading
+            ///
+            /// ## Usage ##
+            /// ### Command-line `--flags`
+            /// ```sh
+            /// # comment, not a heading
+            /// ```
+            /// ## Usage
+            /// #hashtag
+        ),
+        "\
+- [Usage](#usage)
+  - [Command-line `--flags`](#command-line---flags)
+- [Usage](#usage-1)
+
+Intro, not a heading
+
+## Usage ##
+### Command-line `--flags`
+```sh
+# comment, not a heading
+```
+## Usage
+#hashtag"
+    );
+    assert_eq!(
+        docstr!(#[emit_toc]
+            /// no headings
+        ),
+        "no headings"
+    );
+}
+
+#[test]
+fn brace_unescape() {
+    assert_eq!(
+        docstr!(#[brace_unescape]
+            /// {{ "key": {{}} }}
+            /// no braces
+        ),
+        "{ \"key\": {} }\nno braces"
+    );
+    assert_eq!(
+        docstr!(#[brace_unescape]
+            /// {{{{}}}}
+        ),
+        docstr!(format!
+            /// {{{{}}}}
+        )
+    );
+    assert_eq!(
+        docstr!(#[brace_unescape] #[subst(NAME = "x")]
+            /// {{NAME}} = {NAME}
+        ),
+        "{NAME} = x"
+    );
+}
+
+#[test]
+fn check_braces() {
+    assert_eq!(
+        docstr!(#[check_braces]
+            /// fn main() {
+            ///     let {x} = y;
+            /// }
+            /// {{ }} {{}}
+        ),
+        "fn main() {\n    let {x} = y;\n}\n{{ }} {{}}"
+    );
+    assert_eq!(
+        docstr!(#[check_braces] format!
+            /// {} {{}}
+            1
+        ),
+        "1 {}"
+    );
+}
+
+#[test]
+fn reverse_lines() {
+    assert_eq!(
+        docstr!(#[reverse_lines]
+            /// a
+            /// b
+            /// c
+        ),
+        "c\nb\na"
+    );
+    assert_eq!(
+        docstr!(#[reverse_lines]
+            ///
+            /// a
+            ///
+            /// b
+            ///
+            ///
+        ),
+        "b\n\na\n\n\n"
+    );
+    assert_eq!(
+        docstr!(#[reverse_lines]
+            ///
+        ),
+        ""
+    );
+    assert_eq!(
+        docstr!(#[reverse_lines] #[wrap = 2]
+            /// a b
+            /// c d
+        ),
+        "c\nd\na\nb"
+    );
+}
+
+#[test]
+fn explicit_trailing() {
+    assert_eq!(
+        docstr!(#[explicit_trailing]
+            /// two spaces  \
+            /// tab	\
+            /// nothing to remove
+            /// backslash \\
+            /// \ in the middle \
+            /// \
+        ),
+        "two spaces  \ntab\t\nnothing to remove\nbackslash \\\n\\ in the middle \n"
+    );
+
+    // The whitespace survives rustfmt, which would remove it without the `\`
+    let lines = docstr!(#[explicit_trailing]
+        /// a   \
+        /// b \
+    );
+    assert_eq!(lines.split('\n').map(str::len).collect::<Vec<_>>(), [4, 2]);
+}
+
+#[test]
+fn number() {
+    assert_eq!(
+        docstr!(#[number] #[number_format = "{n}| "]
+            /// first
+            /// second
+        ),
+        "1| first\n2| second"
+    );
+    assert_eq!(
+        docstr!(#[number]
+            /// a
+            ///
+            /// b
+        ),
+        "1: a\n2: \n3: b"
+    );
+    assert_eq!(
+        docstr!(#[number] #[number_skip_blank] #[number_from = 0] #[number_format = "[{n}/{n}] "]
+            /// a
+            ///
+            /// b
+        ),
+        "[0/0] a\n\n[1/1] b"
+    );
+}
+
+#[test]
+fn raw() {
+    assert_eq!(
+        docstr!(#[raw] #[trim_trailing_comma] stringify!
+            /// plain
+        ),
+        "r\"plain\""
+    );
+    assert_eq!(
+        docstr!(#[raw] #[trim_trailing_comma] stringify!
+            /// \n is not a newline
+            /// "#
+        ),
+        "r##\"\\n is not a newline\n\"#\"##"
+    );
+    assert_eq!(
+        docstr!(#[raw] #[trim_trailing_comma] stringify!
+            /// "a" "##b"
+        ),
+        "r###\"\"a\" \"##b\"\"###"
+    );
+    assert_eq!(
+        docstr!(#[raw] #[trim_trailing_comma] stringify!
+            #[doc = " carriage\r"]
+        ),
+        "\"carriage\\r\""
+    );
+    assert_eq!(
+        docstr!(#[raw]
+            /// "quoted" \
+        ),
+        "\"quoted\" \\"
+    );
+}
+
+#[test]
+fn with_location() {
+    let line = line!() + 1;
+    let message = docstr!(#[with_location]
+        /// config file is missing
+    );
+    assert_eq!(
+        message,
+        format!("{}:{line} config file is missing", file!())
+    );
+    assert!(message.starts_with("tests/test.rs:"));
+
+    // `format!` accepts `concat!` as its format string
+    let line = line!() + 1;
+    let message = docstr!(#[with_location] format!
+        /// {} is missing
+        "config.toml"
+    );
+    assert_eq!(
+        message,
+        format!("{}:{line} config.toml is missing", file!())
+    );
+
+    // Still a `&'static str`
+    const MESSAGE: &str = docstr!(#[with_location]
+        /// a
+    );
+    assert!(MESSAGE.ends_with(" a"));
+}
+
+#[test]
+fn lines() {
+    assert_eq!(
+        docstr!(#[lines = 1..3]
+            /// 0
+            /// 1
+            /// 2
+            /// 3
+            /// 4
+        ),
+        "1\n2"
+    );
+    assert_eq!(
+        docstr!(#[lines = 3..=4]
+            /// 0
+            /// 1
+            /// 2
+            /// 3
+            /// 4
+        ),
+        "3\n4"
+    );
+    assert_eq!(
+        docstr!(#[lines = ..2]
+            /// 0
+            /// 1
+            /// 2
+        ),
+        "0\n1"
+    );
+    assert_eq!(
+        docstr!(#[lines = 1..]
+            /// 0
+            /// 1
+            /// 2
+        ),
+        "1\n2"
+    );
+    assert_eq!(
+        docstr!(#[lines = 0..3] #[dedent]
+            ///     0
+            ///       1
+            ///
+            /// 3
+        ),
+        "0\n  1\n"
+    );
+    assert_eq!(
+        docstr!(#[lines = 2..2] #[allow_empty]
+            /// 0
+            /// 1
+        ),
+        ""
+    );
+}
+
+#[test]
+fn strip_ansi() {
+    assert_eq!(
+        docstr!(#[strip_ansi]
+            /// \x1b[31mred
+            /// \x1B[1;4mbold\u{1b}[0m \e[K\033[2J
+            #[doc = " \x1b[32mgreen\x1b[m"]
+        ),
+        "red\nbold \ngreen"
+    );
+    // Only complete sequences are removed
+    assert_eq!(
+        docstr!(#[strip_ansi]
+            /// \x1b[31é [0m \x1b \x1b[
+            /// \x1bx \\x1b
+        ),
+        "\\x1b[31é [0m \\x1b \\x1b[\n\\x1bx \\\\x1b"
+    );
+}
+
+#[test]
+fn strip_comments() {
+    assert_eq!(
+        docstr!(#[strip_comments = "#"]
+            /// # note
+            /// echo 1
+            /// echo 2 # kept
+        ),
+        "echo 1\necho 2 # kept"
+    );
+    assert_eq!(
+        docstr!(#[strip_comments = "--"] #[trim_blank_lines]
+            /// -- header
+            ///
+            /// SELECT 1;
+            /// 	-- indented
+            /// - not a comment
+        ),
+        "SELECT 1;\n- not a comment"
+    );
+}
+
+#[test]
+fn trim_blank_lines() {
+    assert_eq!(
+        docstr!(#[trim_blank_lines]
+            ///
+            /// a
+            ///
+        ),
+        "a"
+    );
+    assert_eq!(
+        docstr!(#[trim_blank_lines]
+            #[doc = "   "]
+            ///
+            /// a
+            ///
+            ///   b
+            #[doc = " \t"]
+        ),
+        "a\n\n  b"
+    );
+    assert_eq!(
+        docstr!(#[trim_blank_lines]
+            ///
+            ///
+        ),
+        ""
+    );
+    assert_eq!(
+        docstr!(#[trim_blank_lines] #[dedent]
+            ///
+            ///     a
+            ///         b
+            ///
+        ),
+        "a\n    b"
+    );
+}
+
+#[test]
+fn dedent_first() {
+    assert_eq!(
+        docstr!(#[dedent_first]
+            ///
+            ///     first
+            ///       deeper
+            ///
+            ///     same
+        ),
+        "\nfirst\n  deeper\n\nsame"
+    );
+    assert_eq!(
+        docstr!(#[dedent_first]
+            ///     first
+            ///   shallower
+            /// none
+            #[doc = "\tdifferent whitespace"]
+            #[doc = "      "]
+        ),
+        "first\n  shallower\nnone\n\tdifferent whitespace\n "
+    );
+    assert_eq!(
+        docstr!(#[dedent_first]
+            /// not indented
+            ///     indented
+        ),
+        "not indented\n    indented"
+    );
+}
+
+#[test]
+fn wrap_urls() {
+    assert_eq!(
+        docstr!(#[wrap = 20]
+            /// See https://example.com/docs/getting-started/installation?lang=en#linux for details
+        ),
+        "See\nhttps://example.com/docs/getting-started/installation?lang=en#linux\nfor details"
+    );
+    assert_eq!(
+        docstr!(#[wrap = 30] #[hang = 2]
+            /// - docs: http://a.b/c-d-e_f_g/h/i/j/k/l
+        ),
+        "- docs:\n  http://a.b/c-d-e_f_g/h/i/j/k/l"
+    );
+}
+
+#[test]
+fn hang() {
+    assert_eq!(
+        docstr!(#[wrap = 16] #[hang = 2]
+            /// - first item of the list
+            ///
+            /// - second
+        ),
+        "- first item of\n  the list\n\n- second"
+    );
+    // indentation of the first line is kept, the hang is counted from the start of the line
+    assert_eq!(
+        docstr!(#[hang = 4] #[wrap = 13]
+            ///   nested item here
+        ),
+        "  nested item\n    here"
+    );
+}
+
+#[test]
+fn strip_doc_links() {
+    assert_eq!(
+        docstr!(#[strip_doc_links]
+            /// See [the docs](https://docs.rs/docstr) and [`String::new`](String::new).
+            /// Nested [[x]](u(r)l) links
+        ),
+        "See the docs and String::new.\nNested [x] links"
+    );
+    assert_eq!(
+        docstr!(#[strip_doc_links]
+            /// Uses [`Vec`], [`HashMap`][map] and [Option]
+            /// A stray ] and an unclosed [ bracket
+        ),
+        "Uses Vec, HashMap and Option\nA stray ] and an unclosed [ bracket"
+    );
+}
+
+#[test]
+fn call() {
+    fn shout(text: &str) -> String {
+        text.to_uppercase()
+    }
+
+    assert_eq!(
+        docstr!(#[call = shout]
+            /// a
+            /// b
+        ),
+        "A\nB"
+    );
+
+    let mut lines = vec!["first"];
+    docstr!(#[call = lines.push]
+        /// line
+    );
+    assert_eq!(lines, ["first", "line"]);
+
+    let boxed: Box<str> = docstr!(#[call = <Box<str>>::from]
+        /// generic
+    );
+    assert_eq!(&*boxed, "generic");
+    assert_eq!(
+        docstr!(#[call = str::len] #[wrap_in("(", ")")]
+            /// four
+        ),
+        6
+    );
+}
+
+#[test]
+fn emit_writeln_sequence() {
+    use std::fmt::Write as _;
+
+    fn write_all(writer: &mut String, writer_name: &str) -> std::fmt::Result {
+        docstr!(#[emit_writeln_sequence] writer
+            /// # {{ {writer_name} }}
+            ///
+            ///     indented
+        );
+        docstr!(#[emit_writeln_sequence] &mut *writer,
+            /// end
+        );
+        Ok(())
+    }
+
+    let mut out = String::new();
+    write_all(&mut out, "writer").unwrap();
+    assert_eq!(out, "# { writer }\n\n    indented\nend\n");
+
+    // io::Write works too
+    fn write_io(out: &mut Vec<u8>) -> std::io::Result<()> {
+        use std::io::Write as _;
+
+        docstr!(#[emit_writeln_sequence] out,
+            /// a
+            /// b
+        );
+        Ok(())
+    }
+
+    let mut bytes = Vec::new();
+    write_io(&mut bytes).unwrap();
+    assert_eq!(bytes, b"a\nb\n");
+}
+
+#[test]
+fn cow() {
+    use std::borrow::Cow;
+
+    let borrowed: Cow<'static, str> = docstr_cow!(
+        /// no {interpolation}
+    );
+    assert!(matches!(borrowed, Cow::Borrowed("no {interpolation}")));
+
+    let x = 4;
+    let owned = docstr_cow!(format!
+        /// x = {x}, y = {}
+        x + 1
+    );
+    assert!(matches!(&owned, Cow::Owned(s) if s == "x = 4, y = 5"));
+
+    let both = [borrowed, owned];
+    assert_eq!(both.concat(), "no {interpolation}x = 4, y = 5");
+}
+
+#[test]
+fn bytes() {
+    const BYTES: &[u8; 4] = docstr_bytes!(
+        /// é
+        /// "
+    );
+    assert_eq!(BYTES, "é\n\"".as_bytes());
+
+    let mut buf = Vec::new();
+    docstr_bytes!(#[extend = buf]
+        /// a
+        /// b
+    );
+    docstr_bytes!(#[extend = &mut buf] #[wrap_in("\n", "")]
+        /// c
+    );
+    assert_eq!(buf, b"a\nb\nc");
+
+    struct Buffers {
+        bytes: Vec<u8>,
+        string: String,
+    }
+    let mut buffers = Buffers {
+        bytes: vec![],
+        string: String::new(),
+    };
+    docstr_bytes!(#[extend = buffers.bytes]
+        /// x
+    );
+    docstr!(#[extend = buffers.string]
+        /// y
+    );
+    assert_eq!(buffers.bytes, b"x");
+    assert_eq!(buffers.string, "y");
+}
+
+#[test]
+fn boxed() {
+    let b: Box<str> = docstr_boxed!(
+        /// a
+        /// b
+    );
+    assert_eq!(&*b, "a\nb");
+
+    let wrapped: Box<str> = docstr_boxed!(#[wrap_in("[", "]")]
+        ///
+    );
+    assert_eq!(wrapped, "[]".into());
+}
+
+#[test]
+fn spanned() {
+    assert_eq!(
+        docstr_spanned!(
+            /// a
+            /// b
+        ),
+        "a\nb"
+    );
+    let x = 1;
+    assert_eq!(
+        docstr_spanned!(format!
+            /// {x} {}
+            2
+        ),
+        "1 2"
+    );
+}
+
+#[test]
+fn tee() {
+    let mut received = Vec::new();
+    macro_rules! record {
+        ($string:literal) => {
+            received.push(($string, 1))
+        };
+    }
+    macro_rules! record_again {
+        ($string:literal) => {
+            received.push(($string, 2))
+        };
+    }
+    macro_rules! identity {
+        ($string:literal) => {
+            $string
+        };
+    }
+
+    // Called in order, and the last one is returned
+    let last: &str = docstr_tee!(record!, record_again!, identity!
+        /// a
+        /// b
+    );
+    assert_eq!(received, [("a\nb", 1), ("a\nb", 2)]);
+    assert_eq!(last, "a\nb");
+
+    // Trailing `,` and modes
+    let name = "Bob";
+    let greeting: String = docstr_tee!(#[join = " "] record!, std::format!,
+        /// hi
+        /// {name}
+    );
+    assert_eq!(greeting, "hi Bob");
+    assert_eq!(received[2], ("hi {name}", 1));
+}
+
+#[test]
+fn placeholder_style_printf() {
+    assert_eq!(
+        docstr!(#[placeholder_style = printf] format!
+            /// %s: %d/%i/%u %5.2f %x %X %o %e %c %ld %%d {literal}
+            "name", 1, -2, 3_u8, 1.5, 254, 254, 8, 1000.0, 'c', 10_i64
+        ),
+        "name: 1/-2/3 1.5 fe FE 10 1e3 c 10 %d {literal}"
+    );
+    // without a macro it's still converted
+    assert_eq!(
+        docstr!(#[placeholder_style = printf]
+            /// 100%% %s
+        ),
+        "100% {}"
+    );
+}
+
+#[test]
+fn single_line() {
+    assert_eq!(docstr!(#[doc = " a"] #[doc = " b"] #[doc = ""]), "a\nb\n");
+    assert_eq!(docstr!(format! #[doc = "{}"] #[doc = "{}"] 1, 2), "1\n2");
+    assert_eq!(
+        docstr!(/// a /// b
+        ),
+        "a /// b"
+    );
+
+    // How macros usually generate doc comments
+    macro_rules! generated {
+        ($($line:literal)*) => {
+            docstr!($(#[doc = $line])*)
+        };
+    }
+    assert_eq!(generated!(" x" " y" "z"), "x\ny\nz");
+
+    macro_rules! generated_expr {
+        ($first:expr, $second:expr) => {
+            docstr!(#[doc = $first $second])
+        };
+    }
+    assert_eq!(generated_expr!(" x", "y"), "xy");
+}
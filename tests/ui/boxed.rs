@@ -0,0 +1,10 @@
+fn main() {
+    let _ = docstr::docstr_boxed!(format!
+        /// {}
+        1
+    );
+
+    docstr::docstr_boxed!(#[emit_enum_variants] enum A
+        /// B
+    );
+}
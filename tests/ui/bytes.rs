@@ -0,0 +1,27 @@
+fn main() {
+    let _ = docstr::docstr_bytes!(std::concat!
+        /// a
+    );
+
+    let _ = docstr::docstr_bytes!(#[chunk_lines(1)]
+        /// a
+    );
+
+    let _ = docstr::docstr_bytes!(#[extend]
+        /// a
+    );
+
+    // invalid escapes
+    docstr::docstr_bytes!(#[unescape]
+        /// \q
+        /// \xF
+        /// \x+F
+        /// trailing \
+        /// ok \x00
+    );
+
+    // unescaping only produces bytes
+    docstr::docstr!(#[unescape]
+        /// \x00
+    );
+}
@@ -0,0 +1,27 @@
+fn main() {
+    let _ = docstr::docstr!(format!
+        #[cfg(unix)]
+        /// {}
+        1
+    );
+
+    docstr::docstr!(#[static_ref = A]
+        #[cfg(unix)]
+        /// a
+    );
+
+    let _ = docstr::docstr!(#[repeat = 2]
+        #[cfg(unix)]
+        /// a
+    );
+
+    let _ = docstr::docstr_bytes!(
+        #[cfg(unix)]
+        /// a
+    );
+
+    let _ = docstr::docstr!(
+        /// a
+        #[cfg(unix)]
+    );
+}
@@ -0,0 +1,9 @@
+fn main() {
+    docstr::docstr!(#[dedent]
+        #[doc = "    spaces"]
+        #[doc = "  two spaces"]
+        #[doc = "\ttab"]
+        #[doc = "  \tmixed"]
+        #[doc = "\t  tab then spaces"]
+    );
+}
@@ -1,3 +1,12 @@
 fn main() {
     docstr::docstr!();
+
+    // the path to the macro is there, but the doc comments are not
+    docstr::docstr!(std::format!);
+    docstr::docstr!(format! "{}", 1);
+
+    // only the missing doc comments are allowed
+    docstr::docstr!(#[allow_empty]
+        #[doc = 1]
+    );
 }
@@ -15,6 +15,16 @@ fn main() {
         #[doc ? ]
     );
 
+    docstr::docstr!(
+        /// hidden
+        #[doc(hidden)]
+    );
+
+    docstr::docstr!(
+        #[doc(alias = "other")]
+        /// aliased
+    );
+
     docstr::docstr!(
         #[doc = true]
     );
@@ -26,4 +36,28 @@ fn main() {
     docstr::docstr!(
         #[doc = b"byte string"]
     );
+
+    docstr::docstr!(
+        #[doc = "valid" "also valid" invalid]
+    );
+
+    // macros are not expanded
+    docstr::docstr!(
+        #[doc = include_str!("file.txt")]
+    );
+
+    docstr::docstr!(
+        #[doc = concat!("a", "b")]
+    );
+
+    // `#` after a doc comment starts another one
+    docstr::docstr!(concat!
+        /// a
+        #b
+    );
+
+    docstr::docstr!(concat!
+        /// a
+        #
+    );
 }
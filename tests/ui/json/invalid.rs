@@ -0,0 +1,19 @@
+fn main() {
+    // missing `,`
+    docstr::docstr!(#[validate = json]
+        /// {
+        ///     "name": "docstr"
+        ///     "tags": []
+        /// }
+    );
+
+    // the error is at the end of the input
+    docstr::docstr!(#[validate = json]
+        /// [1, 2,
+        ///  3
+    );
+
+    docstr::docstr!(#[validate = yaml]
+        /// a: b
+    );
+}
@@ -0,0 +1,14 @@
+fn main() {
+    let _ = docstr::docstr_map!(
+        /// a = 1
+        /// b
+    );
+
+    let _ = docstr::docstr_map!(#[delimiter = ""]
+        /// a = 1
+    );
+
+    let _ = docstr::docstr!(#[delimiter = "="]
+        /// a = 1
+    );
+}
@@ -0,0 +1,259 @@
+fn main() {
+    docstr::docstr!(#[emit_match_arms = true]
+        /// _ => 0
+    );
+
+    docstr::docstr!(#[emit_match_arms] #[emit_match_arms]
+        /// _ => 0
+    );
+
+    docstr::docstr!(#[emit_match_arms]
+        /// _ => 0
+    );
+
+    docstr::docstr!(#[emit_match_arms] 1
+        /// _ => (
+    );
+
+    docstr::docstr_with_len!(#[emit_match_arms] 1
+        /// _ => 0
+    );
+
+    docstr::docstr!(#[tabs = 0]
+        /// x
+    );
+
+    docstr::docstr!(#[tabs = "4"]
+        /// x
+    );
+
+    docstr::docstr!(#[tabs = 4] #[tabs = 4]
+        /// x
+    );
+
+    docstr::docstr!(#[wrap_in("only one")]
+        /// x
+    );
+
+    docstr::docstr!(#[wrap_in = "open"]
+        /// x
+    );
+
+    docstr::docstr!(#[normalize_newlines = trailing]
+        /// x
+    );
+
+    docstr::docstr!(#[ensure_shebang = bash]
+        /// x
+    );
+
+    docstr::docstr!(#[into = w]
+        /// x
+    );
+
+    docstr::docstr!(#[into] write!
+        /// x
+    );
+
+    docstr::docstr!(#[wrap_preserve_indent]
+        /// x
+    );
+
+    docstr::docstr!(#[hang = 2]
+        /// x
+    );
+
+    docstr::docstr!(#[emit_writeln_sequence]
+        /// x
+    );
+
+    docstr::docstr!(#[emit_enum_variants] enum Bad
+        /// Good
+        /// not valid
+        /// 1
+        /// Good
+    );
+
+    docstr::docstr!(#[emit_enum_variants = Debug]
+        /// A
+    );
+
+    docstr::docstr!(#[emit_static_str_map_sorted = MAP] #[strict_keys]
+        /// b: 1
+        /// no colon
+        /// a: 2
+        /// b: 3
+    );
+
+    docstr::docstr!(#[strict_keys]
+        /// x
+    );
+
+    docstr::docstr!(#[call]
+        /// x
+    );
+
+    docstr::docstr!(#[call = String::from] format!
+        /// x
+    );
+
+    docstr::docstr!(#[emit_byte_match] 1_u8
+        /// 0x100 => 1
+        /// 'a' => 2
+        /// -1 => 3
+        /// 1_u16 => 4
+        /// _ => 0x100
+    );
+
+    docstr::docstr!(#[subst(A = "a", A = "b", B = "b")]
+        /// {A} {B} {C}
+    );
+
+    docstr::docstr!(#[subst(A = 1)]
+        /// x
+    );
+
+    docstr::docstr!(#[strip = -1]
+        /// x
+    );
+
+    docstr::docstr!(#[as_diff = changed]
+        /// x
+    );
+
+    docstr::docstr!(#[diff_markers] #[as_diff = added]
+        /// x
+    );
+
+    docstr::docstr!(#[join = ' ']
+        /// x
+    );
+
+    docstr::docstr!(#[placeholder_style = c]
+        /// x
+    );
+
+    docstr::docstr!(#[placeholder_style = printf]
+        /// %s %n %
+    );
+
+    docstr::docstr!(#[at_placeholder] format!
+        /// x
+    );
+
+    docstr::docstr!(#[at_placeholder] format! @, @
+        /// x
+        @
+    );
+
+    docstr::docstr!(#[at_placeholder]
+        /// x
+    );
+
+    docstr::docstr!(#[repeat = 0]
+        /// x
+    );
+
+    docstr::docstr!(#[repeat = n]
+        /// x
+    );
+
+    docstr::docstr!(#[upper] #[lower]
+        /// x
+    );
+
+    docstr::docstr!(#[dedent] #[dedent_first]
+        /// x
+    );
+
+    docstr::docstr!(#[brace_unescape]
+        /// {x}
+        /// }}{
+        /// }
+    );
+
+    docstr::docstr!(#[brace_unescape] format!
+        /// x
+    );
+
+    docstr::docstr!(#[lines = 1..3]
+        /// x
+        /// y
+    );
+
+    docstr::docstr!(#[lines = 2..1]
+        /// x
+    );
+
+    docstr::docstr!(#[lines = 1..=]
+        /// x
+    );
+
+    docstr::docstr!(#[lines = 1]
+        /// x
+    );
+
+    docstr::docstr!(#[lines = 0..=18446744073709551615]
+        /// x
+    );
+
+    docstr::docstr!(#[strip_comments = '#']
+        /// x
+    );
+
+    docstr::docstr!(#[strip_comments = ""]
+        /// x
+    );
+
+    docstr::docstr!(#[number_from = 1] #[number_skip_blank]
+        /// x
+    );
+
+    docstr::docstr!(#[number] #[number_format = "#"]
+        /// x
+    );
+
+    docstr::docstr!([d, x, dedent]
+        /// x
+    );
+
+    docstr::docstr!([d, d] #[trim_blank_lines] #[dedent]
+        /// x
+    );
+
+    docstr::docstr!([d t]
+        /// x
+    );
+
+    docstr::docstr!(#[dedent, "x", trim_blank_lines]
+        /// x
+    );
+
+    docstr::docstr!(#[dedent, dedent = 1]
+        /// x
+    );
+
+    docstr::docstr!(#[join_expr = ", "] #[join = ", "]
+        /// x
+    );
+
+    docstr::docstr!(#[join_expr]
+        /// x
+    );
+
+    docstr::docstr!(#[with_location] #[static_ref = A]
+        /// x
+    );
+
+    let _ = docstr::docstr_bytes!(#[with_location]
+        /// x
+    );
+
+    docstr::docstr!(#[strip = 2] #[space_strip = all]
+        /// x
+    );
+
+    docstr::docstr!(#[space_strip = some]
+        /// x
+    );
+}
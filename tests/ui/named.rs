@@ -0,0 +1,14 @@
+fn main() {}
+
+// the same string twice, even with a different name
+docstr::docstr!(#[named = LICENSE]
+    /// MIT
+);
+
+docstr::docstr!(#[named = COPY]
+    /// MIT
+);
+
+docstr::docstr!(#[named = format!]
+    /// MIT
+);
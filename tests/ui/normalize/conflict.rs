@@ -0,0 +1,5 @@
+fn main() {
+    docstr::docstr!(#[nfc] #[nfd]
+        /// x
+    );
+}
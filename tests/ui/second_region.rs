@@ -0,0 +1,25 @@
+fn main() {
+    let _ = docstr::docstr!(
+        /// a
+        1
+        /// b
+    );
+
+    let _ = docstr::docstr!(#[repeat = 2] concat!
+        /// a
+        1
+        /// b
+    );
+
+    docstr::docstr!(#[static_ref = A]
+        /// a
+        1
+        /// b
+    );
+
+    let _ = docstr::docstr_lines_count!(
+        /// a
+        1
+        /// b
+    );
+}
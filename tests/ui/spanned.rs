@@ -0,0 +1,10 @@
+fn main() {
+    let _: u8 = docstr::docstr_spanned!(
+        /// not a number
+        /// at all
+    );
+
+    let _ = docstr::docstr_spanned!(format!
+        /// {missing}
+    );
+}
@@ -0,0 +1,22 @@
+fn main() {
+    docstr::docstr_tee!(
+        /// a
+    );
+
+    docstr::docstr_tee!(println!, print
+        /// a
+    );
+
+    docstr::docstr_tee!(println!, print!
+        /// {}
+        1
+    );
+
+    docstr::docstr_tee!(eprintln!, !
+        /// a
+    );
+
+    docstr::docstr_tee!(eprintln!, std::print println!
+        /// a
+    );
+}
@@ -0,0 +1,19 @@
+fn main() {
+    docstr::docstr!(#[validate_no_trailing_whitespace]
+        /// clean
+        #[doc = " trailing space "]
+        /// clean
+        #[doc = " trailing tab\t"]
+    );
+
+    docstr::docstr!(#[check_braces]
+        /// } {
+        /// {
+        /// {"a":{}}
+    );
+
+    // 2 unclosed braces on the same line are reported once
+    docstr::docstr!(#[check_braces]
+        /// { {
+    );
+}
@@ -12,4 +12,26 @@ fn main() {
         /// {}
         "world"
     );
+
+    // forgot the exclamation mark right before the doc comments
+    docstr::docstr!(String
+        /// hello
+    );
+
+    docstr::docstr!(std::format
+        /// hello
+    );
+
+    // absolute paths
+    docstr::docstr!(::std::format
+        /// hello
+    );
+
+    docstr::docstr!(::std::format s
+        /// hello
+    );
+
+    docstr::docstr!(!
+        /// hello
+    );
 }